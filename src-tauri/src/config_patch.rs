@@ -0,0 +1,110 @@
+//! Partial config updates, written into config.toml in place so the user's comments,
+//! formatting and unknown keys survive
+
+use serde_json::{Map, Value};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// Keys whose value differs between two serialized configs: the new value, or null
+/// for a key `new` no longer has. Nested tables are compared key by key.
+pub fn diff(old: &Value, new: &Value) -> Map<String, Value> {
+    let empty = Map::new();
+    let old = old.as_object().unwrap_or(&empty);
+    let new = new.as_object().unwrap_or(&empty);
+
+    let mut patch = Map::new();
+    for (key, old_value) in old {
+        if !new.contains_key(key) {
+            patch.insert(key.clone(), Value::Null);
+        } else if let (Value::Object(_), Value::Object(_)) = (old_value, &new[key]) {
+            let nested = diff(old_value, &new[key]);
+            if !nested.is_empty() {
+                patch.insert(key.clone(), Value::Object(nested));
+            }
+        } else if *old_value != new[key] {
+            patch.insert(key.clone(), new[key].clone());
+        }
+    }
+    for (key, new_value) in new {
+        if !old.contains_key(key) {
+            patch.insert(key.clone(), new_value.clone());
+        }
+    }
+    patch
+}
+
+/// Merge `patch` into `base`: objects merge key by key, null removes a key, anything
+/// else replaces the value
+pub fn merge(base: &mut Map<String, Value>, patch: &Map<String, Value>) {
+    for (key, value) in patch {
+        match (base.get_mut(key), value) {
+            (_, Value::Null) => {
+                base.remove(key);
+            }
+            (Some(Value::Object(existing)), Value::Object(nested)) => merge(existing, nested),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Apply `patch` to a parsed config.toml, touching only the keys it names
+pub fn apply(doc: &mut DocumentMut, patch: &Map<String, Value>) {
+    apply_to_table(doc.as_table_mut(), patch, true);
+}
+
+/// New tables become `[section]`s at the top level and inline tables below it
+fn apply_to_table(table: &mut dyn TableLike, patch: &Map<String, Value>, top_level: bool) {
+    for (key, value) in patch {
+        match value {
+            Value::Null => {
+                table.remove(key);
+            }
+            Value::Object(nested) => match table.get_mut(key).and_then(Item::as_table_like_mut) {
+                Some(existing) => apply_to_table(existing, nested, false),
+                None if nested.values().all(Value::is_null) => {}
+                None if top_level => {
+                    let mut section = toml_edit::Table::new();
+                    apply_to_table(&mut section, nested, false);
+                    table.insert(key, Item::Table(section));
+                }
+                None => {
+                    if let Some(value) = toml_value(value) {
+                        table.insert(key, Item::Value(value));
+                    }
+                }
+            },
+            _ => {
+                if let Some(value) = toml_value(value) {
+                    table.insert(key, Item::Value(value));
+                }
+            }
+        }
+    }
+}
+
+/// A JSON value as a TOML value. TOML has no null, so nulls (and arrays of them) are
+/// dropped.
+fn toml_value(value: &Value) -> Option<toml_edit::Value> {
+    match value {
+        Value::Null => None,
+        Value::Bool(b) => Some((*b).into()),
+        Value::Number(n) => n
+            .as_i64()
+            .map(toml_edit::Value::from)
+            .or_else(|| n.as_f64().map(toml_edit::Value::from)),
+        Value::String(s) => Some(s.as_str().into()),
+        Value::Array(items) => Some(toml_edit::Value::Array(
+            items.iter().filter_map(toml_value).collect(),
+        )),
+        Value::Object(map) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (key, value) in map {
+                if let Some(value) = toml_value(value) {
+                    table.insert(key.as_str(), value);
+                }
+            }
+            Some(table.into())
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! CSV/TSV parsing for table rendering
+
+/// Parsed delimited data, padded to a rectangular shape
+pub struct DelimitedTable {
+    pub rows: Vec<Vec<String>>,
+    /// Whether rows or columns were dropped to stay within the caps
+    pub truncated: bool,
+}
+
+/// Guess the delimiter from the first line, preferring tab, then comma, then semicolon
+pub fn detect_delimiter(content: &str) -> char {
+    let first_line = content.lines().next().unwrap_or_default();
+    ['\t', ',', ';']
+        .into_iter()
+        .max_by_key(|d| count_unquoted(first_line, *d))
+        .filter(|d| count_unquoted(first_line, *d) > 0)
+        .unwrap_or(',')
+}
+
+fn count_unquoted(line: &str, delimiter: char) -> usize {
+    let mut in_quotes = false;
+    line.chars()
+        .filter(|c| {
+            if *c == '"' {
+                in_quotes = !in_quotes;
+            }
+            *c == delimiter && !in_quotes
+        })
+        .count()
+}
+
+/// Parse delimited text, handling quoted fields (with `""` escapes and embedded
+/// newlines). Ragged rows are padded; output is capped at `max_rows` x `max_cols`.
+pub fn parse_delimited(
+    content: &str,
+    delimiter: char,
+    max_rows: usize,
+    max_cols: usize,
+) -> DelimitedTable {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut truncated = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                // Skip blank lines
+                if row.len() == 1 && row[0].is_empty() {
+                    row.clear();
+                    continue;
+                }
+                rows.push(std::mem::take(&mut row));
+                if rows.len() > max_rows {
+                    break;
+                }
+            }
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+
+    // Final row without a trailing newline
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    if rows.len() > max_rows {
+        rows.truncate(max_rows);
+        truncated = true;
+    }
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if width > max_cols {
+        truncated = true;
+    }
+    let width = width.min(max_cols);
+    for row in &mut rows {
+        row.truncate(width);
+        row.resize(width, String::new());
+    }
+
+    DelimitedTable { rows, truncated }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Vec<Vec<String>> {
+        parse_delimited(content, ',', 100, 100).rows
+    }
+
+    #[test]
+    fn detects_the_delimiter_from_the_first_line() {
+        assert_eq!(detect_delimiter("a\tb\tc\n1,2,3\n"), '\t');
+        assert_eq!(detect_delimiter("a,b,c\n"), ',');
+        assert_eq!(detect_delimiter("a;b;\"x,y\"\n"), ';');
+        assert_eq!(detect_delimiter("single\n"), ',');
+    }
+
+    #[test]
+    fn keeps_quoted_commas_and_escaped_quotes() {
+        assert_eq!(
+            parse("name,quote\n\"Doe, Jane\",\"She said \"\"hi\"\"\"\n"),
+            vec![
+                vec!["name".to_string(), "quote".to_string()],
+                vec!["Doe, Jane".to_string(), "She said \"hi\"".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_newlines_inside_quoted_fields() {
+        assert_eq!(
+            parse("a,b\r\n\"line one\nline two\",x\r\n"),
+            vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["line one\nline two".to_string(), "x".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn pads_ragged_rows_and_skips_blank_lines() {
+        let table = parse_delimited("a,b,c\n\n1\n2,3", ',', 100, 100);
+        assert_eq!(
+            table.rows,
+            vec![
+                vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                vec!["1".to_string(), String::new(), String::new()],
+                vec!["2".to_string(), "3".to_string(), String::new()],
+            ]
+        );
+        assert!(!table.truncated);
+    }
+
+    #[test]
+    fn caps_rows_and_columns() {
+        let table = parse_delimited("1,2,3\n4,5,6\n7,8,9\n", ',', 2, 2);
+        assert_eq!(
+            table.rows,
+            vec![
+                vec!["1".to_string(), "2".to_string()],
+                vec!["4".to_string(), "5".to_string()],
+            ]
+        );
+        assert!(table.truncated);
+    }
+}
@@ -0,0 +1,78 @@
+//! Directory mode: the supported files under a folder, for the file sidebar
+
+use std::fs;
+use std::path::Path;
+
+/// Files listed beyond this are dropped, so pointing glance at a huge tree stays fast
+const MAX_DIRECTORY_FILES: usize = 5000;
+
+/// Subdirectories deeper than this are not scanned
+const MAX_DIRECTORY_DEPTH: usize = 16;
+
+/// A file in the directory listing
+#[derive(Clone, serde::Serialize)]
+pub struct FileEntry {
+    /// Absolute path, as passed to open_dropped_file
+    pub path: String,
+    /// Path relative to the listed directory, with `/` separators
+    pub relative_path: String,
+    /// File name
+    pub name: String,
+}
+
+/// Recursively list the files under `dir` that `is_supported` accepts, sorted by
+/// relative path. Hidden files and directories (`.git`, `.obsidian`, ...) and symlinked
+/// directories are skipped.
+pub fn scan(dir: &Path, is_supported: &dyn Fn(&Path) -> bool) -> Vec<FileEntry> {
+    let mut entries = Vec::new();
+    scan_into(dir, dir, 0, is_supported, &mut entries);
+    entries.sort_by(|a, b| {
+        a.relative_path
+            .to_lowercase()
+            .cmp(&b.relative_path.to_lowercase())
+    });
+    entries
+}
+
+fn scan_into(
+    root: &Path,
+    dir: &Path,
+    depth: usize,
+    is_supported: &dyn Fn(&Path) -> bool,
+    entries: &mut Vec<FileEntry>,
+) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in read_dir.flatten() {
+        if entries.len() >= MAX_DIRECTORY_FILES {
+            return;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            if depth < MAX_DIRECTORY_DEPTH {
+                scan_into(root, &path, depth + 1, is_supported, entries);
+            }
+        } else if is_supported(&path) {
+            let relative_path = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            entries.push(FileEntry {
+                path: path.to_string_lossy().to_string(),
+                relative_path,
+                name,
+            });
+        }
+    }
+}
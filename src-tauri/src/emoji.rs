@@ -0,0 +1,271 @@
+//! Emoji shortcode (`:rocket:`) replacement
+
+use crate::markdown::is_fence_line;
+
+/// Built-in shortcode map, sorted by shortcode for binary search
+const SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("alarm_clock", "⏰"),
+    ("angry", "😠"),
+    ("apple", "🍎"),
+    ("arrow_down", "⬇️"),
+    ("arrow_left", "⬅️"),
+    ("arrow_right", "➡️"),
+    ("arrow_up", "⬆️"),
+    ("art", "🎨"),
+    ("baby", "👶"),
+    ("balloon", "🎈"),
+    ("bangbang", "‼️"),
+    ("beer", "🍺"),
+    ("bell", "🔔"),
+    ("bike", "🚲"),
+    ("bird", "🐦"),
+    ("blush", "😊"),
+    ("bomb", "💣"),
+    ("book", "📖"),
+    ("bookmark", "🔖"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("cake", "🍰"),
+    ("calendar", "📆"),
+    ("camera", "📷"),
+    ("car", "🚗"),
+    ("cat", "🐱"),
+    ("chart_with_upwards_trend", "📈"),
+    ("check", "✔️"),
+    ("checkered_flag", "🏁"),
+    ("clap", "👏"),
+    ("clipboard", "📋"),
+    ("clock", "🕐"),
+    ("closed_lock_with_key", "🔐"),
+    ("cloud", "☁️"),
+    ("coffee", "☕"),
+    ("computer", "💻"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cool", "🆒"),
+    ("cry", "😢"),
+    ("dart", "🎯"),
+    ("dizzy", "💫"),
+    ("dog", "🐶"),
+    ("email", "📧"),
+    ("exclamation", "❗"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("flags", "🎏"),
+    ("floppy_disk", "💾"),
+    ("gear", "⚙️"),
+    ("gem", "💎"),
+    ("gift", "🎁"),
+    ("globe_with_meridians", "🌐"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("hammer", "🔨"),
+    ("hammer_and_wrench", "🛠️"),
+    ("hand", "✋"),
+    ("hankey", "💩"),
+    ("heart", "❤️"),
+    ("heart_eyes", "😍"),
+    ("heavy_check_mark", "✔️"),
+    ("heavy_minus_sign", "➖"),
+    ("heavy_plus_sign", "➕"),
+    ("hourglass", "⌛"),
+    ("house", "🏠"),
+    ("hugs", "🤗"),
+    ("information_source", "ℹ️"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("laughing", "😆"),
+    ("link", "🔗"),
+    ("lipstick", "💄"),
+    ("lock", "🔒"),
+    ("loudspeaker", "📢"),
+    ("mag", "🔍"),
+    ("memo", "📝"),
+    ("moneybag", "💰"),
+    ("moon", "🌙"),
+    ("muscle", "💪"),
+    ("new", "🆕"),
+    ("no_entry", "⛔"),
+    ("no_entry_sign", "🚫"),
+    ("ok", "🆗"),
+    ("ok_hand", "👌"),
+    ("package", "📦"),
+    ("page_facing_up", "📄"),
+    ("paperclip", "📎"),
+    ("partying_face", "🥳"),
+    ("pencil", "📝"),
+    ("pencil2", "✏️"),
+    ("penguin", "🐧"),
+    ("point_down", "👇"),
+    ("point_left", "👈"),
+    ("point_right", "👉"),
+    ("point_up", "☝️"),
+    ("pray", "🙏"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("rainbow", "🌈"),
+    ("raised_hands", "🙌"),
+    ("recycle", "♻️"),
+    ("red_circle", "🔴"),
+    ("relaxed", "☺️"),
+    ("rocket", "🚀"),
+    ("rotating_light", "🚨"),
+    ("scissors", "✂️"),
+    ("see_no_evil", "🙈"),
+    ("shield", "🛡️"),
+    ("shipit", "🐿️"),
+    ("skull", "💀"),
+    ("sleeping", "😴"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("smirk", "😏"),
+    ("snake", "🐍"),
+    ("snowflake", "❄️"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("speech_balloon", "💬"),
+    ("star", "⭐"),
+    ("star2", "🌟"),
+    ("stop_sign", "🛑"),
+    ("sunglasses", "😎"),
+    ("sunny", "☀️"),
+    ("sweat_smile", "😅"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("trophy", "🏆"),
+    ("truck", "🚚"),
+    ("umbrella", "☔"),
+    ("unlock", "🔓"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("zap", "⚡"),
+    ("zzz", "💤"),
+];
+
+/// Look up the emoji for a shortcode name (without colons)
+fn lookup(name: &str) -> Option<&'static str> {
+    SHORTCODES
+        .binary_search_by(|(code, _)| (*code).cmp(name))
+        .ok()
+        .map(|i| SHORTCODES[i].1)
+}
+
+fn is_shortcode_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '+' | '-')
+}
+
+/// Replace known `:shortcode:` sequences with emoji, leaving code fences,
+/// inline code spans, and unknown shortcodes untouched
+pub fn replace_shortcodes(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut in_code_block = false;
+
+    for line in content.split_inclusive('\n') {
+        if is_fence_line(line) {
+            in_code_block = !in_code_block;
+            out.push_str(line);
+            continue;
+        }
+
+        if in_code_block {
+            out.push_str(line);
+        } else {
+            replace_in_line(line, &mut out);
+        }
+    }
+
+    out
+}
+
+fn replace_in_line(line: &str, out: &mut String) {
+    let mut rest = line;
+
+    while let Some(pos) = rest.find([':', '`']) {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        if rest.starts_with('`') {
+            // Copy an inline code span verbatim
+            let run = rest.chars().take_while(|c| *c == '`').count();
+            let fence = &rest[..run];
+            let end = rest[run..]
+                .find(fence)
+                .map(|i| run + i + run)
+                .unwrap_or(run);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        // rest starts with ':'
+        let name_len = rest[1..]
+            .chars()
+            .take_while(|c| is_shortcode_char(*c))
+            .count();
+        let name = &rest[1..1 + name_len];
+        if name_len > 0 && rest[1 + name_len..].starts_with(':') {
+            if let Some(emoji) = lookup(name) {
+                out.push_str(emoji);
+                rest = &rest[name_len + 2..];
+                continue;
+            }
+        }
+
+        // Not a known shortcode: keep the colon and continue scanning after it
+        out.push(':');
+        rest = &rest[1..];
+    }
+
+    out.push_str(rest);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortcodes_are_sorted_for_binary_search() {
+        assert!(SHORTCODES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn replaces_known_shortcodes_and_keeps_unknown_ones() {
+        assert_eq!(
+            replace_shortcodes("Ship it :rocket: :not_an_emoji: :tada:\n"),
+            "Ship it 🚀 :not_an_emoji: 🎉\n"
+        );
+        assert_eq!(replace_shortcodes(":+1::smile:"), "👍😄");
+    }
+
+    #[test]
+    fn leaves_colons_that_are_not_shortcodes() {
+        assert_eq!(replace_shortcodes("Time 10:30:45"), "Time 10:30:45");
+        assert_eq!(replace_shortcodes("a :: b :rocket"), "a :: b :rocket");
+        assert_eq!(replace_shortcodes(":Rocket:"), ":Rocket:");
+    }
+
+    #[test]
+    fn skips_code_fences_and_inline_code() {
+        let content = "before :rocket:\n```\n:rocket:\n```\nafter `:rocket:` :zap:\n";
+        assert_eq!(
+            replace_shortcodes(content),
+            "before 🚀\n```\n:rocket:\n```\nafter `:rocket:` ⚡\n"
+        );
+    }
+
+    #[test]
+    fn unclosed_inline_code_does_not_hide_the_rest() {
+        assert_eq!(replace_shortcodes("a ` b :zap:"), "a ` b ⚡");
+    }
+}
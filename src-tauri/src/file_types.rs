@@ -0,0 +1,139 @@
+//! The file types glance opens: the extension allowlist and text detection
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Extensions always opened as markdown
+pub const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+/// How the frontend should render the current file
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderMode {
+    Markdown,
+    Plantuml,
+    Table,
+    /// Shown as source text: no AsciiDoc renderer is bundled
+    Asciidoc,
+}
+
+/// Decide how a file is rendered from its (last, case-insensitive) extension, or None
+/// if glance doesn't open it. This is the single extension allowlist: CSV/TSV only
+/// count when `csv` is enabled, and `extra_extensions` are treated as markdown.
+pub fn classify(path: &Path, csv: bool, extra_extensions: &[String]) -> Option<RenderMode> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        ext if MARKDOWN_EXTENSIONS.contains(&ext) => Some(RenderMode::Markdown),
+        "puml" | "plantuml" => Some(RenderMode::Plantuml),
+        "adoc" | "asciidoc" => Some(RenderMode::Asciidoc),
+        "csv" | "tsv" if csv => Some(RenderMode::Table),
+        other
+            if extra_extensions
+                .iter()
+                .any(|ext| ext.trim().trim_start_matches('.').to_lowercase() == other) =>
+        {
+            Some(RenderMode::Markdown)
+        }
+        _ => None,
+    }
+}
+
+/// How much of a file is checked for NUL bytes before reading the rest
+const BINARY_SNIFF_LEN: usize = 8 * 1024;
+
+/// Read a file as UTF-8 text. A NUL byte near the start fails fast as binary without
+/// reading the whole file; either way a non-text file is an `InvalidData` error.
+pub fn read_text(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut bytes = Vec::new();
+    file.by_ref()
+        .take(BINARY_SNIFF_LEN as u64)
+        .read_to_end(&mut bytes)?;
+    if bytes.contains(&0) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "file appears to be binary",
+        ));
+    }
+
+    file.read_to_end(&mut bytes)?;
+    String::from_utf8(bytes)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "file is not valid UTF-8 text"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, process};
+
+    fn mode(name: &str) -> Option<RenderMode> {
+        classify(Path::new(name), false, &[])
+    }
+
+    #[test]
+    fn classify_ignores_extension_case() {
+        assert_eq!(mode("README.MD"), Some(RenderMode::Markdown));
+        assert_eq!(mode("notes.Markdown"), Some(RenderMode::Markdown));
+        assert_eq!(mode("flow.PUML"), Some(RenderMode::Plantuml));
+        assert_eq!(mode("guide.AsciiDoc"), Some(RenderMode::Asciidoc));
+    }
+
+    #[test]
+    fn classify_rejects_missing_extensions() {
+        assert_eq!(mode("README"), None);
+        assert_eq!(mode("md"), None);
+        assert_eq!(mode(".md"), None);
+        assert_eq!(mode("notes."), None);
+    }
+
+    #[test]
+    fn classify_uses_only_the_last_extension() {
+        assert_eq!(mode("notes.md.txt"), None);
+        assert_eq!(mode("notes.txt.md"), Some(RenderMode::Markdown));
+        assert_eq!(mode("archive.md.gz"), None);
+    }
+
+    #[test]
+    fn classify_honors_csv_and_extra_extensions() {
+        assert_eq!(mode("data.csv"), None);
+        assert_eq!(
+            classify(Path::new("data.TSV"), true, &[]),
+            Some(RenderMode::Table)
+        );
+        let extra = vec![".MDX".to_string(), " txt ".to_string()];
+        assert_eq!(
+            classify(Path::new("page.mdx"), false, &extra),
+            Some(RenderMode::Markdown)
+        );
+        assert_eq!(
+            classify(Path::new("notes.md.txt"), false, &extra),
+            Some(RenderMode::Markdown)
+        );
+    }
+
+    #[test]
+    fn read_text_rejects_binary_and_invalid_utf8() {
+        let dir = env::temp_dir().join(format!("glance-read-text-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let text = dir.join("text.md");
+        let binary = dir.join("binary.md");
+        let latin1 = dir.join("latin1.md");
+        fs::write(&text, "# Caf\u{e9}\n").unwrap();
+        fs::write(&binary, b"# PNG\x00\x01\x02").unwrap();
+        fs::write(&latin1, b"# Caf\xe9\n").unwrap();
+
+        assert_eq!(read_text(&text).unwrap(), "# Caf\u{e9}\n");
+        for path in [&binary, &latin1] {
+            assert_eq!(
+                read_text(path).unwrap_err().kind(),
+                io::ErrorKind::InvalidData
+            );
+        }
+        assert_eq!(
+            read_text(&dir.join("missing.md")).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
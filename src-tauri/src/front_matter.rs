@@ -0,0 +1,128 @@
+//! YAML front matter (`---` ... `---` at the top of a document)
+
+use serde_yaml::{Mapping, Value};
+
+/// Front matter block found at the very start of a document
+pub struct FrontMatter {
+    /// Number of lines the block occupies, including both fences
+    pub line_count: usize,
+    /// YAML between the fences, as written
+    pub raw: String,
+    /// Parsed top-level mapping
+    pub values: Mapping,
+}
+
+/// Parse a leading front matter block. Only a `---` on the first line opens one,
+/// and the body must be a YAML mapping, so horizontal rules are never mistaken for it.
+pub fn parse_front_matter(content: &str) -> Option<FrontMatter> {
+    let mut lines = content.trim_start_matches('\u{feff}').lines();
+    if lines.next()?.trim_end() != "---" {
+        return None;
+    }
+
+    let mut yaml_lines = Vec::new();
+    for line in lines {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            let raw = yaml_lines.join("\n");
+            let values = if raw.trim().is_empty() {
+                Mapping::new()
+            } else {
+                serde_yaml::from_str(&raw).ok()?
+            };
+            return Some(FrontMatter {
+                line_count: yaml_lines.len() + 2,
+                raw,
+                values,
+            });
+        }
+        yaml_lines.push(line);
+    }
+
+    None
+}
+
+impl FrontMatter {
+    /// Top-level key/value pairs as display strings
+    pub fn entries(&self) -> Vec<(String, String)> {
+        self.values
+            .iter()
+            .map(|(key, value)| (value_to_string(key), value_to_string(value)))
+            .collect()
+    }
+
+    /// The block as a standalone `---` fenced YAML header
+    pub fn to_yaml_block(&self) -> String {
+        if self.raw.is_empty() {
+            "---\n---\n".to_string()
+        } else {
+            format!("---\n{}\n---\n", self.raw)
+        }
+    }
+}
+
+/// The document after its front matter block
+pub fn body<'a>(content: &'a str, front_matter: &FrontMatter) -> &'a str {
+    let offset: usize = content
+        .split_inclusive('\n')
+        .take(front_matter.line_count)
+        .map(str::len)
+        .sum();
+    &content[offset..]
+}
+
+/// Render a YAML value as display text: scalars as-is, sequences comma-joined,
+/// nested mappings as inline YAML
+pub fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Sequence(items) => items
+            .iter()
+            .map(value_to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Mapping(_) => serde_yaml::to_string(value)
+            .map(|s| s.trim().replace('\n', "; "))
+            .unwrap_or_default(),
+        Value::Tagged(tagged) => value_to_string(&tagged.value),
+    }
+}
+
+/// Replace the front matter with blank lines so line numbers stay aligned with the file
+pub fn strip(content: &str, front_matter: &FrontMatter) -> String {
+    replace_block(content, front_matter, |_, _| String::new())
+}
+
+/// Turn the front matter fences into a ```yaml code block (same line count)
+pub fn as_code_block(content: &str, front_matter: &FrontMatter) -> String {
+    let last = front_matter.line_count - 1;
+    replace_block(content, front_matter, |i, line| match i {
+        0 => "```yaml".to_string(),
+        i if i == last => "```".to_string(),
+        _ => line.to_string(),
+    })
+}
+
+fn replace_block(
+    content: &str,
+    front_matter: &FrontMatter,
+    map_line: impl Fn(usize, &str) -> String,
+) -> String {
+    let mut out = String::with_capacity(content.len());
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        if i < front_matter.line_count {
+            let (text, newline) = match line.strip_suffix('\n') {
+                Some(text) => (text.trim_end_matches('\r'), "\n"),
+                None => (line, ""),
+            };
+            out.push_str(&map_line(i, text));
+            out.push_str(newline);
+        } else {
+            out.push_str(line);
+        }
+    }
+    out
+}
@@ -0,0 +1,42 @@
+//! Server-side syntax highlighting of code blocks with syntect
+
+use crate::render::escape_html;
+use std::sync::OnceLock;
+use syntect::highlighting::ThemeSet;
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+/// Theme used when `code_theme` isn't set
+const DEFAULT_THEME: &str = "InspiredGitHub";
+
+// Loading the bundled definitions takes a noticeable moment, so do it once, on first use
+static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+
+fn syntaxes() -> &'static SyntaxSet {
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn themes() -> &'static ThemeSet {
+    THEMES.get_or_init(ThemeSet::load_defaults)
+}
+
+/// Names of the bundled themes, sorted
+pub fn theme_names() -> Vec<String> {
+    themes().themes.keys().cloned().collect()
+}
+
+/// Highlight `code` as `lang` (a fence token like "rust" or "py") to a `<pre>` with
+/// inline styles. An unknown language or theme gives a plain, escaped `<pre>`.
+pub fn highlight(lang: &str, code: &str, theme: Option<&str>) -> String {
+    let syntaxes = syntaxes();
+    let syntax = syntaxes.find_syntax_by_token(lang.trim());
+    let theme = themes().themes.get(theme.unwrap_or(DEFAULT_THEME));
+    let highlighted = match (syntax, theme) {
+        (Some(syntax), Some(theme)) => {
+            highlighted_html_for_string(code, syntaxes, syntax, theme).ok()
+        }
+        _ => None,
+    };
+    highlighted.unwrap_or_else(|| format!("<pre><code>{}</code></pre>\n", escape_html(code)))
+}
@@ -0,0 +1,345 @@
+//! Daemon IPC transports: Unix domain sockets, Windows named pipes, and a TCP
+//! localhost fallback. Messages are UTF-8 strings in both directions.
+
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+#[cfg(unix)]
+use directories::ProjectDirs;
+#[cfg(unix)]
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::PathBuf;
+
+/// Localhost port for the TCP fallback transport
+const DEFAULT_TCP_PORT: u16 = 47291;
+
+/// How long a client waits for the TCP daemon to accept
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// How long the daemon waits on a connected client that has stopped sending
+const CLIENT_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a client waits on a daemon that has stopped replying. A read past it
+/// fails with `WouldBlock` or `TimedOut`, see is_timeout.
+const DAEMON_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A connected, bidirectional IPC stream
+pub trait IpcStream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> IpcStream for T {}
+
+/// Accepts incoming daemon connections
+pub trait IpcListener: Send {
+    /// Wait for the next client. Socket streams come back with CLIENT_READ_TIMEOUT set;
+    /// named pipes have no read timeout.
+    fn accept(&self) -> io::Result<Box<dyn IpcStream>>;
+}
+
+/// How a glance instance reaches the running daemon
+pub trait IpcTransport: Send + Sync {
+    /// Connect to a listening daemon. Reads from the stream time out after
+    /// DAEMON_READ_TIMEOUT, so a hung daemon can't hang the client.
+    fn connect(&self) -> io::Result<Box<dyn IpcStream>>;
+
+    /// Start listening, replacing a stale endpoint left by a crashed daemon
+    fn listen(&self) -> io::Result<Box<dyn IpcListener>>;
+
+    /// Human-readable endpoint: socket path, pipe name, or address
+    fn endpoint(&self) -> String;
+
+    /// Whether the endpoint is present (the socket file, for Unix sockets)
+    fn endpoint_exists(&self) -> bool {
+        self.connect().is_ok()
+    }
+
+    /// Remove the endpoint when the daemon exits
+    fn cleanup(&self) {}
+}
+
+/// Whether a read failed because its timeout expired: `WouldBlock` on Unix sockets,
+/// `TimedOut` elsewhere
+pub fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+/// Pick the transport for the current OS: a Unix socket or a named pipe, falling
+/// back to TCP on localhost where neither is available
+pub fn default_transport() -> Box<dyn IpcTransport> {
+    platform_transport().unwrap_or_else(|| {
+        Box::new(TcpTransport {
+            port: DEFAULT_TCP_PORT,
+        })
+    })
+}
+
+#[cfg(unix)]
+fn platform_transport() -> Option<Box<dyn IpcTransport>> {
+    socket_path().map(|path| Box::new(UnixSocketTransport { path }) as Box<dyn IpcTransport>)
+}
+
+#[cfg(windows)]
+fn platform_transport() -> Option<Box<dyn IpcTransport>> {
+    // Per-user pipe name so different users' daemons don't collide
+    let user = std::env::var("USERNAME").unwrap_or_default();
+    Some(Box::new(NamedPipeTransport {
+        name: format!(r"\\.\pipe\glance-{}", user),
+    }))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn platform_transport() -> Option<Box<dyn IpcTransport>> {
+    None
+}
+
+/// Get the path to the IPC socket for daemon mode
+#[cfg(unix)]
+fn socket_path() -> Option<PathBuf> {
+    ProjectDirs::from("com", "glance", "glance").and_then(|dirs| {
+        // Try runtime_dir first, fall back to cache_dir
+        dirs.runtime_dir()
+            .map(|dir| dir.join("glance.sock"))
+            .or_else(|| Some(dirs.cache_dir().join("glance.sock")))
+    })
+}
+
+/// Unix domain socket in the runtime (or cache) directory
+#[cfg(unix)]
+struct UnixSocketTransport {
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl IpcTransport for UnixSocketTransport {
+    fn connect(&self) -> io::Result<Box<dyn IpcStream>> {
+        let stream = UnixStream::connect(&self.path)?;
+        stream.set_read_timeout(Some(DAEMON_READ_TIMEOUT))?;
+        Ok(Box::new(stream))
+    }
+
+    fn listen(&self) -> io::Result<Box<dyn IpcListener>> {
+        // Create parent directories if needed
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        match UnixListener::bind(&self.path) {
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse && self.is_stale() => {
+                fs::remove_file(&self.path)?;
+                Ok(Box::new(UnixListener::bind(&self.path)?))
+            }
+            result => Ok(Box::new(result?)),
+        }
+    }
+
+    fn endpoint(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+
+    fn endpoint_exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    fn cleanup(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(unix)]
+impl UnixSocketTransport {
+    /// Whether the socket file was left behind by a daemon that's gone: nothing is
+    /// listening on it, so connecting is refused
+    fn is_stale(&self) -> bool {
+        matches!(
+            UnixStream::connect(&self.path),
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused
+        )
+    }
+}
+
+#[cfg(unix)]
+impl IpcListener for UnixListener {
+    fn accept(&self) -> io::Result<Box<dyn IpcStream>> {
+        let (stream, _) = UnixListener::accept(self)?;
+        stream.set_read_timeout(Some(CLIENT_READ_TIMEOUT))?;
+        Ok(Box::new(stream))
+    }
+}
+
+/// TCP on 127.0.0.1. Any local user can connect, so this is only the fallback.
+struct TcpTransport {
+    port: u16,
+}
+
+impl TcpTransport {
+    fn addr(&self) -> SocketAddr {
+        SocketAddr::from((Ipv4Addr::LOCALHOST, self.port))
+    }
+}
+
+impl IpcTransport for TcpTransport {
+    fn connect(&self) -> io::Result<Box<dyn IpcStream>> {
+        let stream = TcpStream::connect_timeout(&self.addr(), TCP_CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(DAEMON_READ_TIMEOUT))?;
+        Ok(Box::new(stream))
+    }
+
+    fn listen(&self) -> io::Result<Box<dyn IpcListener>> {
+        Ok(Box::new(TcpListener::bind(self.addr())?))
+    }
+
+    fn endpoint(&self) -> String {
+        self.addr().to_string()
+    }
+}
+
+impl IpcListener for TcpListener {
+    fn accept(&self) -> io::Result<Box<dyn IpcStream>> {
+        let (stream, _) = TcpListener::accept(self)?;
+        stream.set_read_timeout(Some(CLIENT_READ_TIMEOUT))?;
+        Ok(Box::new(stream))
+    }
+}
+
+/// Windows named pipe (`\\.\pipe\glance-<user>`)
+#[cfg(windows)]
+struct NamedPipeTransport {
+    name: String,
+}
+
+#[cfg(windows)]
+impl IpcTransport for NamedPipeTransport {
+    fn connect(&self) -> io::Result<Box<dyn IpcStream>> {
+        // The client end of a named pipe is opened like a file
+        let pipe = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.name)?;
+        Ok(Box::new(TimedPipe {
+            pipe,
+            timeout: DAEMON_READ_TIMEOUT,
+        }))
+    }
+
+    fn listen(&self) -> io::Result<Box<dyn IpcListener>> {
+        Ok(Box::new(NamedPipeListener {
+            name: self.name.encode_utf16().chain(Some(0)).collect(),
+        }))
+    }
+
+    fn endpoint(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// Client end of a named pipe with a read timeout. Synchronous pipe handles have
+/// none, so reads wait for data with PeekNamedPipe first.
+#[cfg(windows)]
+struct TimedPipe {
+    pipe: std::fs::File,
+    timeout: Duration,
+}
+
+#[cfg(windows)]
+impl Read for TimedPipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        use std::os::windows::io::AsRawHandle;
+        use windows_sys::Win32::System::Pipes::PeekNamedPipe;
+
+        let deadline = std::time::Instant::now() + self.timeout;
+        loop {
+            let mut available = 0u32;
+            // SAFETY: the handle is open for the life of `self.pipe`, and only the
+            // byte count is written
+            let peeked = unsafe {
+                PeekNamedPipe(
+                    self.pipe.as_raw_handle() as _,
+                    std::ptr::null_mut(),
+                    0,
+                    std::ptr::null_mut(),
+                    &mut available,
+                    std::ptr::null_mut(),
+                )
+            };
+            // A closed pipe fails the peek; the read reports it
+            if peeked == 0 || available > 0 {
+                return self.pipe.read(buf);
+            }
+            if std::time::Instant::now() >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "timed out waiting for the daemon",
+                ));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Write for TimedPipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pipe.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.pipe.flush()
+    }
+}
+
+/// Serves a named pipe by creating a fresh pipe instance per connection
+#[cfg(windows)]
+struct NamedPipeListener {
+    /// NUL-terminated UTF-16 pipe name
+    name: Vec<u16>,
+}
+
+#[cfg(windows)]
+impl IpcListener for NamedPipeListener {
+    fn accept(&self) -> io::Result<Box<dyn IpcStream>> {
+        use std::os::windows::io::FromRawHandle;
+        use windows_sys::Win32::Foundation::{
+            GetLastError, ERROR_PIPE_CONNECTED, INVALID_HANDLE_VALUE,
+        };
+        use windows_sys::Win32::Storage::FileSystem::PIPE_ACCESS_DUPLEX;
+        use windows_sys::Win32::System::Pipes::{
+            ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+        };
+
+        // SAFETY: `name` is NUL-terminated and outlives the call
+        let handle = unsafe {
+            CreateNamedPipeW(
+                self.name.as_ptr(),
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                std::ptr::null(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: the handle is valid and owned from here on, so it is closed on every path
+        let pipe = unsafe { std::fs::File::from_raw_handle(handle as _) };
+
+        // Blocks until a client connects; a client that raced ahead is also fine
+        // SAFETY: synchronous pipe, so no OVERLAPPED is needed
+        let connected = unsafe { ConnectNamedPipe(handle, std::ptr::null_mut()) };
+        if connected == 0 && unsafe { GetLastError() } != ERROR_PIPE_CONNECTED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Box::new(pipe))
+    }
+}
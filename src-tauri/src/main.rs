@@ -1,32 +1,165 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod config_patch;
+mod delimited;
+mod directory;
+mod emoji;
+mod file_types;
+mod front_matter;
+mod highlight;
+mod ipc;
+mod markdown;
+mod pdf;
+mod plaintext;
+mod plantuml;
+mod render;
+mod search;
+mod slug;
+
 use directories::ProjectDirs;
+use file_types::RenderMode;
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
-use std::io::{Read, Write};
-use std::os::unix::net::{UnixListener, UnixStream};
-use std::path::PathBuf;
+use std::hash::{Hash, Hasher};
+use std::io::{IsTerminal, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
-use tauri::{Manager, Emitter};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::menu::{IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::DialogExt;
 
-/// Threshold for large file mode (500KB)
+/// Default threshold for large file mode (500KB), see `large_file_threshold`
 const LARGE_FILE_THRESHOLD: u64 = 500 * 1024;
 
-/// Get the path to the IPC socket for daemon mode
-fn get_socket_path() -> Option<PathBuf> {
-    ProjectDirs::from("com", "glance", "glance").and_then(|dirs| {
-        // Try runtime_dir first, fall back to cache_dir
-        dirs.runtime_dir()
-            .map(|dir| dir.join("glance.sock"))
-            .or_else(|| Some(dirs.cache_dir().join("glance.sock")))
-    })
-}
+/// How often the daemon checks whether its idle timeout has elapsed
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Prefix of a daemon message that carries file content along with the path
+const FRAME_MAGIC: &str = "GLANCE-FRAME ";
+
+/// Maximum size of a framed message's header and path lines
+const MAX_FRAME_HEADER: usize = 64 * 1024;
+
+/// Maximum content accepted in a framed daemon message (64MB)
+const MAX_FRAMED_CONTENT: usize = 64 * 1024 * 1024;
+
+/// Maximum content accepted from a remote client (8MB). Anyone who can reach the
+/// port can send, so this is far lower than the local limit.
+const MAX_REMOTE_CONTENT: usize = 8 * 1024 * 1024;
+
+/// Remote clients served at once; connections past this are refused
+const MAX_REMOTE_CLIENTS: usize = 8;
+
+/// How long a remote client may take to send its frame before it is dropped
+const REMOTE_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for piped input to start when no file is given, so a launcher's
+/// idle pipe can't hang startup (`glance -` waits as long as it takes)
+const STDIN_START_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Default re-run interval for `--cmd` mode (seconds)
+const DEFAULT_COMMAND_INTERVAL_SECS: u64 = 2;
+
+/// Maximum command output rendered in `--cmd` mode (10MB)
+const MAX_COMMAND_OUTPUT: usize = 10 * 1024 * 1024;
+
+/// Largest image load_image reads or an export embeds when `max_image_size` isn't set (20MB)
+const DEFAULT_MAX_IMAGE_SIZE: u64 = 20 * 1024 * 1024;
+
+/// Reading speed for document_stats when `reading_wpm` isn't set
+const DEFAULT_READING_WPM: u32 = 200;
+
+/// Rendered PlantUML diagrams kept before the cache is cleared
+const MAX_CACHED_DIAGRAMS: usize = 128;
+
+/// Row and column caps for CSV/TSV table rendering
+const MAX_TABLE_ROWS: usize = 5000;
+const MAX_TABLE_COLUMNS: usize = 100;
+
+/// Heading count above which the TOC sidebar is shown by default
+const TOC_AUTO_MIN_HEADINGS: usize = 3;
+
+/// Pipe tables wider than this (columns, or characters in any row) are flagged for
+/// horizontal-scroll affordances
+const WIDE_TABLE_COLUMNS: usize = 8;
+const WIDE_TABLE_CHARS: usize = 120;
+
+/// Maximum number of entries kept in the link navigation history
+const MAX_HISTORY: usize = 50;
+
+/// Cap on blocks returned by get_paragraphs, so large files stay responsive
+const MAX_PARAGRAPHS: usize = 5000;
+
+/// How often the watcher compares the file's mtime against the last one seen,
+/// to catch changes missed by a stale watch (e.g. after sleep/wake)
+const STALE_WATCH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long the watcher treats the file's content as glance's own save (toggle_task
+/// and other writes through AppState::write_document) rather than an external edit
+const SELF_WRITE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Default quiet period after file events before reloading (milliseconds)
+const DEFAULT_WATCH_DEBOUNCE_MS: u64 = 150;
+
+/// Quiet period before a directory-mode file list refresh, and the longest a burst
+/// of events can hold it back
+const DIRECTORY_EVENT_DEBOUNCE: Duration = Duration::from_millis(200);
+const DIRECTORY_EVENT_MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// Quiet period after a config file event before the config is reloaded, so an
+/// editor's write-rename-chmod sequence reloads once
+const CONFIG_EVENT_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Maximum number of entries kept in the recent-files list
+const MAX_RECENT_FILES: usize = 20;
+
+/// Recent files listed in the tray menu
+const TRAY_RECENT_FILES: usize = 10;
+
+/// ID of the daemon's tray icon
+const TRAY_ID: &str = "main";
+
+/// Remembered scroll positions beyond this prune deleted files, then the oldest
+const MAX_SCROLL_POSITIONS: usize = 200;
+
+/// Flags that consume the following argument as their value
+const VALUE_FLAGS: &[&str] = &[
+    "--cmd",
+    "--interval",
+    "--dump-html",
+    "--section",
+    "--large-file-threshold",
+    "--port",
+    "--css",
+];
+
+/// Smallest usable window size; restored sizes are clamped up to this
+const MIN_WINDOW_WIDTH: u32 = 400;
+const MIN_WINDOW_HEIGHT: u32 = 300;
+
+/// Saved window geometry beyond these bounds is treated as corrupt
+const MAX_WINDOW_DIMENSION: u32 = 16384;
+const MAX_WINDOW_OFFSET: u32 = 20000;
+
+/// How much of the title bar must land on a monitor for a restored window to count
+/// as reachable
+const TITLE_BAR_HEIGHT: u32 = 32;
+const MIN_VISIBLE_TITLE_WIDTH: u32 = 64;
+
+/// Minimum gap between window-state writes while the window is moved or resized
+const WINDOW_SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
 
 /// Window state for persistence
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
@@ -35,6 +168,11 @@ struct WindowState {
     y: i32,
     width: u32,
     height: u32,
+    /// Position and size above are the un-maximized geometry to return to
+    #[serde(default)]
+    maximized: bool,
+    #[serde(default)]
+    fullscreen: bool,
 }
 
 impl Default for WindowState {
@@ -44,6 +182,8 @@ impl Default for WindowState {
             y: 100,
             width: 900,
             height: 700,
+            maximized: false,
+            fullscreen: false,
         }
     }
 }
@@ -54,6 +194,246 @@ impl WindowState {
             .map(|dirs| dirs.config_dir().join("window.json"))
     }
 
+    /// Load the saved state, falling back to the default for bogus values and
+    /// clamping degenerate sizes up to the minimum
+    fn load() -> Self {
+        let mut state: Self = Self::config_path()
+            .and_then(|path| fs::read_to_string(&path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .filter(Self::is_plausible)
+            .unwrap_or_default();
+        state.width = state.width.max(MIN_WINDOW_WIDTH);
+        state.height = state.height.max(MIN_WINDOW_HEIGHT);
+        // Only one can apply; a hand-edited or stale file gets the less intrusive one
+        if state.maximized && state.fullscreen {
+            state.fullscreen = false;
+        }
+        state
+    }
+
+    /// Reject sizes and positions no real display produces (corrupt JSON, or the
+    /// offscreen coordinates some platforms report for minimized windows)
+    fn is_plausible(&self) -> bool {
+        self.width <= MAX_WINDOW_DIMENSION
+            && self.height <= MAX_WINDOW_DIMENSION
+            && self.x.unsigned_abs() <= MAX_WINDOW_OFFSET
+            && self.y.unsigned_abs() <= MAX_WINDOW_OFFSET
+    }
+
+    /// Fit the saved geometry to the connected monitors' work areas. Falls back to the
+    /// default when no monitor shows enough of the title bar to drag the window back,
+    /// and shrinks a window larger than the monitor it lands on.
+    fn fit_to_monitors(&self, work_areas: &[ScreenRect]) -> Self {
+        // Monitor enumeration failed; nothing to check against
+        if work_areas.is_empty() {
+            return self.clone();
+        }
+
+        let title_bar = ScreenRect {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: TITLE_BAR_HEIGHT.min(self.height),
+        };
+        let best = work_areas
+            .iter()
+            .filter_map(|area| Some((area, title_bar.intersection(area)?)))
+            .filter(|(_, overlap)| overlap.width >= MIN_VISIBLE_TITLE_WIDTH.min(self.width))
+            .max_by_key(|(_, overlap)| overlap.area());
+        let Some((area, _)) = best else {
+            return Self::default();
+        };
+
+        let width = self.width.min(area.width).max(MIN_WINDOW_WIDTH);
+        let height = self.height.min(area.height).max(MIN_WINDOW_HEIGHT);
+        let mut x = self.x;
+        let mut y = self.y;
+        // A shrunk window is moved fully onto the monitor
+        if width < self.width {
+            x = x.clamp(area.x, (area.right() - width as i32).max(area.x));
+        }
+        if height < self.height {
+            y = y.clamp(area.y, (area.bottom() - height as i32).max(area.y));
+        }
+        // A title bar above the top edge can't be grabbed
+        y = y.max(area.y);
+
+        Self {
+            x,
+            y,
+            width,
+            height,
+            maximized: self.maximized,
+            fullscreen: self.fullscreen,
+        }
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(path) = Self::config_path() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let content = serde_json::to_string_pretty(self)?;
+            fs::write(&path, content)?;
+        }
+        Ok(())
+    }
+}
+
+/// A rectangle in physical screen coordinates
+#[derive(Clone, Copy)]
+struct ScreenRect {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+impl ScreenRect {
+    fn right(&self) -> i32 {
+        self.x.saturating_add_unsigned(self.width)
+    }
+
+    fn bottom(&self) -> i32 {
+        self.y.saturating_add_unsigned(self.height)
+    }
+
+    fn area(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    fn intersection(&self, other: &ScreenRect) -> Option<ScreenRect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        (right > x && bottom > y).then(|| ScreenRect {
+            x,
+            y,
+            width: right.abs_diff(x),
+            height: bottom.abs_diff(y),
+        })
+    }
+}
+
+/// Work areas (screen minus taskbar/dock) of every connected monitor
+fn monitor_work_areas(window: &tauri::WebviewWindow) -> Vec<ScreenRect> {
+    match window.available_monitors() {
+        Ok(monitors) => monitors
+            .iter()
+            .map(|monitor| {
+                let area = monitor.work_area();
+                ScreenRect {
+                    x: area.position.x,
+                    y: area.position.y,
+                    width: area.size.width,
+                    height: area.size.height,
+                }
+            })
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to list monitors: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Entry in the recent-files list
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct RecentFileEntry {
+    /// Canonical path of the file
+    path: String,
+    /// When the file was last opened (Unix seconds)
+    opened_at: u64,
+}
+
+/// Recently opened files for persistence, most recent first
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+struct RecentFiles {
+    #[serde(default)]
+    entries: Vec<RecentFileEntry>,
+}
+
+impl RecentFiles {
+    fn config_path() -> Option<PathBuf> {
+        ProjectDirs::from("com", "glance", "glance")
+            .map(|dirs| dirs.config_dir().join("recent.json"))
+    }
+
+    fn load() -> Self {
+        Self::config_path()
+            .and_then(|path| fs::read_to_string(&path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(path) = Self::config_path() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let content = serde_json::to_string_pretty(self)?;
+            fs::write(&path, content)?;
+        }
+        Ok(())
+    }
+
+    /// Move `path` to the front of the list, de-duplicating and capping the length
+    fn push(&mut self, path: &str) {
+        let opened_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.entries.retain(|entry| entry.path != path);
+        self.entries.insert(
+            0,
+            RecentFileEntry {
+                path: path.to_string(),
+                opened_at,
+            },
+        );
+        self.entries.truncate(MAX_RECENT_FILES);
+    }
+
+    /// Record an opened file in the persisted list
+    fn record(path: &str) {
+        let mut recent = Self::load();
+        recent.push(path);
+        if let Err(e) = recent.save() {
+            eprintln!("Failed to save recent files: {}", e);
+        }
+    }
+
+    /// Most recently opened file that still exists on disk
+    fn most_recent_existing(&self) -> Option<String> {
+        self.entries
+            .iter()
+            .find(|entry| PathBuf::from(&entry.path).exists())
+            .map(|entry| entry.path.clone())
+    }
+}
+
+/// Last scroll offset of a file
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct ScrollPosition {
+    offset: f64,
+    /// Unix timestamp (seconds) when the offset was saved
+    saved_at: u64,
+}
+
+/// Per-file scroll offsets keyed by canonical path
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+struct ScrollPositions {
+    #[serde(default)]
+    positions: HashMap<String, ScrollPosition>,
+}
+
+impl ScrollPositions {
+    fn config_path() -> Option<PathBuf> {
+        ProjectDirs::from("com", "glance", "glance")
+            .map(|dirs| dirs.config_dir().join("scroll_positions.json"))
+    }
+
     fn load() -> Self {
         Self::config_path()
             .and_then(|path| fs::read_to_string(&path).ok())
@@ -71,6 +451,40 @@ impl WindowState {
         }
         Ok(())
     }
+
+    /// Record `offset` for `path`, pruning when the map grows past the cap
+    fn set(&mut self, path: &str, offset: f64) {
+        let saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.positions
+            .insert(path.to_string(), ScrollPosition { offset, saved_at });
+
+        if self.positions.len() > MAX_SCROLL_POSITIONS {
+            self.positions.retain(|path, _| Path::new(path).exists());
+        }
+        if self.positions.len() > MAX_SCROLL_POSITIONS {
+            let mut by_age: Vec<(u64, String)> = self
+                .positions
+                .iter()
+                .map(|(path, position)| (position.saved_at, path.clone()))
+                .collect();
+            by_age.sort();
+            let excess = self.positions.len() - MAX_SCROLL_POSITIONS;
+            for (_, path) in by_age.into_iter().take(excess) {
+                self.positions.remove(&path);
+            }
+        }
+    }
+}
+
+/// Key for per-file state: the canonical path, or the path as given if it can't be
+/// resolved
+fn per_file_key(path: &str) -> String {
+    fs::canonicalize(path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string())
 }
 
 /// Extension configuration
@@ -78,6 +492,26 @@ impl WindowState {
 struct ExtensionsConfig {
     #[serde(default)]
     plantuml: bool,
+    /// PlantUML server for render_plantuml when no local PlantUML is installed
+    /// (None = the public plantuml.com server)
+    #[serde(default)]
+    plantuml_server: Option<String>,
+    /// plantuml.jar to render with via `java -jar`, preferred over anything on PATH
+    #[serde(default)]
+    plantuml_jar: Option<String>,
+    /// Report ```mermaid fences to the frontend, including ones whose info string
+    /// (```Mermaid, ```mermaid {attrs}) doesn't yield a language-mermaid class
+    #[serde(default)]
+    mermaid: bool,
+    /// Replace `:shortcode:` sequences with emoji
+    #[serde(default)]
+    emoji: bool,
+    /// Open .csv/.tsv files and render them as tables
+    #[serde(default)]
+    csv: bool,
+    /// Resolve `[[Page]]` wikilinks to markdown files next to the document
+    #[serde(default)]
+    wikilinks: bool,
 }
 
 /// Application configuration from config.toml
@@ -87,20 +521,429 @@ struct AppConfig {
     no_truncate: bool,
     #[serde(default)]
     extensions: ExtensionsConfig,
+    /// Disable accordion/scroll animations (None = follow the system preference)
+    #[serde(default)]
+    reduce_motion: Option<bool>,
+    /// Use the single-instance daemon: hand files to a running glance, and become the
+    /// daemon when none is running (None = true). `--daemon`/`--no-daemon` override it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    daemon: Option<bool>,
+    /// Exit the daemon after the window has been hidden this long (None = never)
+    #[serde(default)]
+    daemon_idle_timeout_minutes: Option<u64>,
+    /// Reopen the most recent file when launched without a file argument
+    #[serde(default)]
+    reopen_last_file: bool,
+    /// Keep the window hidden on launch until the frontend has rendered, for at most
+    /// this many milliseconds (None = show immediately)
+    #[serde(default)]
+    defer_show_ms: Option<u64>,
+    /// Max width of the rendered content as a CSS length, e.g. "72ch" (None = full width)
+    #[serde(default)]
+    content_max_width: Option<String>,
+    /// Show the TOC sidebar (None = show when the outline has more than a few entries)
+    #[serde(default)]
+    show_toc: Option<bool>,
+    /// What closing the window does: "hide" (default), "quit", or "ask"
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    close_behavior: String,
+    /// How YAML front matter is shown: "hide" (default), "table", or "raw"
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    front_matter_display: String,
+    /// What to do when the daemon is asked to open the file it already shows:
+    /// "focus" (default, keeps the scroll position) or "reload"
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    reopen_same_file: String,
+    /// What to do when the open file is deleted: "keep" (default, shows stale content),
+    /// "prev" (open the previous file in the navigation history), or "close" (hide the window)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    on_delete: String,
+    /// Prefix headings with hierarchical numbers (1, 1.1, 1.1.1)
+    #[serde(default)]
+    number_headings: bool,
+    /// Keyboard shortcut overrides, action name -> accelerator (e.g. zoom_in = "CmdOrCtrl+K")
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    shortcuts: HashMap<String, String>,
+    /// Additional file extensions to open as markdown, e.g. ["qmd", "rmd"]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extra_extensions: Vec<String>,
+    /// Size above which files open in sectioned large-file mode: bytes, or a string
+    /// like "1MB". 0 disables large-file mode (None = 500KB).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    large_file_threshold: Option<ByteSize>,
+    /// Wait this long after the last file event before reloading, so a burst of events
+    /// from one save renders once (milliseconds, None = 150)
+    #[serde(default)]
+    watch_debounce_ms: Option<u64>,
+    /// Address to accept markdown pushed from other machines on, e.g. "0.0.0.0:47292".
+    /// Off unless set. There is no authentication: anyone who can reach the address
+    /// can replace what the window shows, so only use it on a trusted network. Remote
+    /// clients may only send content frames, never paths to open on this machine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    remote_listen: Option<String>,
+    /// Theme for highlight_code, e.g. "InspiredGitHub" (default) or "base16-ocean.dark";
+    /// see list_code_themes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code_theme: Option<String>,
+    /// Reading speed for document_stats' reading time, in words per minute (None = 200)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reading_wpm: Option<u32>,
+    /// Stylesheet applied on top of the built-in styles; a relative path is resolved
+    /// against the config directory. `--css` overrides it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    custom_css: Option<PathBuf>,
+    /// Largest image load_image reads or an export embeds: bytes, or a string like
+    /// "5MB" (None = 20MB)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_image_size: Option<ByteSize>,
+    /// Color theme: "light", "dark", or "system" (default, follows the OS)
+    #[serde(default, skip_serializing_if = "Theme::is_system")]
+    theme: Theme,
+}
+
+/// Color theme of the window
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Theme {
+    Light,
+    Dark,
+    /// Follow the OS color scheme (also used for unknown values)
+    #[default]
+    #[serde(other)]
+    System,
+}
+
+impl Theme {
+    fn is_system(&self) -> bool {
+        *self == Theme::System
+    }
+
+    /// The theme to force on the window, or None to follow the OS
+    fn window_theme(self) -> Option<tauri::Theme> {
+        match self {
+            Theme::Light => Some(tauri::Theme::Light),
+            Theme::Dark => Some(tauri::Theme::Dark),
+            Theme::System => None,
+        }
+    }
+}
+
+/// Payload of the `theme-changed` event
+#[derive(Clone, serde::Serialize)]
+struct ThemeChanged {
+    /// The configured theme
+    theme: Theme,
+    /// Whether the content should render dark, with `system` resolved against the OS
+    dark: bool,
+}
+
+impl ThemeChanged {
+    fn new(theme: Theme, os_theme: tauri::Theme) -> Self {
+        let dark = match theme {
+            Theme::Light => false,
+            Theme::Dark => true,
+            Theme::System => os_theme == tauri::Theme::Dark,
+        };
+        ThemeChanged { theme, dark }
+    }
 }
 
+/// A size in config.toml, either a plain byte count or a string like "512KB"
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum ByteSize {
+    Bytes(u64),
+    Text(String),
+}
+
+/// How YAML front matter is presented
+#[derive(Clone, Copy, PartialEq)]
+enum FrontMatterDisplay {
+    /// Strip it from the rendered body
+    Hide,
+    /// Strip it and return the parsed key/values for a metadata card
+    Table,
+    /// Leave it in the body as a YAML code block
+    Raw,
+}
+
+/// Action taken when the open file is deleted
+#[derive(Clone, Copy, PartialEq)]
+enum OnDelete {
+    /// Keep the stale content and emit `file-deleted`
+    Keep,
+    /// Open the previous file in the navigation history (falls back to keep)
+    Prev,
+    /// Hide the window
+    Close,
+}
+
+/// Action taken when the daemon is handed the file it already shows
+#[derive(Clone, Copy, PartialEq)]
+enum ReopenSameFile {
+    /// Raise the window without re-reading, preserving scroll position
+    Focus,
+    /// Re-read the file and re-emit `file-loaded`
+    Reload,
+}
+
+/// Action taken when the main window is closed
+#[derive(Clone, Copy, PartialEq)]
+enum CloseBehavior {
+    /// Hide the window and keep the daemon running
+    Hide,
+    /// Quit the app
+    Quit,
+    /// Ask the frontend via a `confirm-close` event
+    Ask,
+}
+
+/// Config file names in lookup order (first found wins)
+const CONFIG_FILE_NAMES: &[&str] = &["config.toml", "config.json", "config.yaml"];
+
 impl AppConfig {
+    /// Path that `save` creates when there is no config file yet (always TOML)
     fn config_path() -> Option<PathBuf> {
         ProjectDirs::from("com", "glance", "glance")
             .map(|dirs| dirs.config_dir().join("config.toml"))
     }
 
+    /// Find the existing config file, trying TOML, then JSON, then YAML
+    fn resolve_config_path() -> Option<PathBuf> {
+        let dirs = ProjectDirs::from("com", "glance", "glance")?;
+        CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| dirs.config_dir().join(name))
+            .find(|path| path.exists())
+    }
+
     fn load() -> Self {
-        Self::config_path()
-            .and_then(|path| fs::read_to_string(&path).ok())
-            .and_then(|content| toml::from_str(&content).ok())
-            .unwrap_or_default()
+        let Some(path) = Self::resolve_config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        let parsed = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&content).map_err(|e| e.to_string()),
+            Some("yaml") => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+            _ => toml::from_str(&content).map_err(|e| e.to_string()),
+        };
+
+        parsed.unwrap_or_else(|e| {
+            eprintln!("Failed to parse config {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    /// `custom_css` as an absolute path
+    fn custom_css_path(&self) -> Option<PathBuf> {
+        let path = self.custom_css.as_ref()?;
+        if path.is_absolute() {
+            return Some(path.clone());
+        }
+        let dirs = ProjectDirs::from("com", "glance", "glance")?;
+        Some(dirs.config_dir().join(path))
+    }
+
+    /// Parse `front_matter_display`, falling back to hide for unset or unknown values
+    fn front_matter_display(&self) -> FrontMatterDisplay {
+        match self.front_matter_display.as_str() {
+            "" | "hide" => FrontMatterDisplay::Hide,
+            "table" => FrontMatterDisplay::Table,
+            "raw" => FrontMatterDisplay::Raw,
+            other => {
+                eprintln!("Unknown front_matter_display '{}', using 'hide'", other);
+                FrontMatterDisplay::Hide
+            }
+        }
+    }
+
+    /// Parse `close_behavior`, falling back to hide for unset or unknown values
+    fn close_behavior(&self) -> CloseBehavior {
+        match self.close_behavior.as_str() {
+            "" | "hide" => CloseBehavior::Hide,
+            "quit" => CloseBehavior::Quit,
+            "ask" => CloseBehavior::Ask,
+            other => {
+                eprintln!("Unknown close_behavior '{}', using 'hide'", other);
+                CloseBehavior::Hide
+            }
+        }
+    }
+
+    /// Parse `on_delete`, falling back to keep for unset or unknown values
+    fn on_delete(&self) -> OnDelete {
+        match self.on_delete.as_str() {
+            "" | "keep" => OnDelete::Keep,
+            "prev" => OnDelete::Prev,
+            "close" => OnDelete::Close,
+            other => {
+                eprintln!("Unknown on_delete '{}', using 'keep'", other);
+                OnDelete::Keep
+            }
+        }
+    }
+
+    /// Parse `reopen_same_file`, falling back to focus for unset or unknown values
+    fn reopen_same_file(&self) -> ReopenSameFile {
+        match self.reopen_same_file.as_str() {
+            "" | "focus" => ReopenSameFile::Focus,
+            "reload" => ReopenSameFile::Reload,
+            other => {
+                eprintln!("Unknown reopen_same_file '{}', using 'focus'", other);
+                ReopenSameFile::Focus
+            }
+        }
+    }
+
+    /// Parse `remote_listen`, ignoring (and reporting) an invalid address
+    fn remote_listen(&self) -> Option<SocketAddr> {
+        let value = self.remote_listen.as_deref()?.trim();
+        match value.parse() {
+            Ok(addr) => Some(addr),
+            Err(_) => {
+                eprintln!("Invalid remote_listen '{}', not listening remotely", value);
+                None
+            }
+        }
+    }
+
+    /// Resolve `large_file_threshold` in bytes, falling back to 500KB for unset or invalid values
+    fn large_file_threshold(&self) -> u64 {
+        match &self.large_file_threshold {
+            None => LARGE_FILE_THRESHOLD,
+            Some(ByteSize::Bytes(bytes)) => *bytes,
+            Some(ByteSize::Text(text)) => parse_byte_size(text).unwrap_or_else(|| {
+                eprintln!("Invalid large_file_threshold '{}', using 500KB", text);
+                LARGE_FILE_THRESHOLD
+            }),
+        }
+    }
+
+    fn max_image_size(&self) -> u64 {
+        match &self.max_image_size {
+            None => DEFAULT_MAX_IMAGE_SIZE,
+            Some(ByteSize::Bytes(bytes)) => *bytes,
+            Some(ByteSize::Text(text)) => parse_byte_size(text).unwrap_or_else(|| {
+                eprintln!("Invalid max_image_size '{}', using 20MB", text);
+                DEFAULT_MAX_IMAGE_SIZE
+            }),
+        }
+    }
+
+    /// Write to the config file `load` reads (see resolve_config_path), or create
+    /// config.toml when there is none. The existing file is edited in place, changing
+    /// only the settings that differ from it, so keys glance doesn't know survive, and
+    /// in config.toml the user's comments and formatting too.
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = Self::resolve_config_path().or_else(Self::config_path) else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let format = path.extension().and_then(|e| e.to_str());
+        let content = match (fs::read_to_string(&path), format) {
+            (Ok(existing), Some("json")) => {
+                let mut doc: serde_json::Value = serde_json::from_str(&existing)?;
+                let on_disk: AppConfig = serde_json::from_value(doc.clone())?;
+                if let serde_json::Value::Object(map) = &mut doc {
+                    config_patch::merge(map, &self.changes_from(&on_disk)?);
+                }
+                serde_json::to_string_pretty(&doc)?
+            }
+            (Ok(existing), Some("yaml")) => {
+                let mut doc: serde_json::Value = serde_yaml::from_str(&existing)?;
+                let on_disk: AppConfig = serde_json::from_value(doc.clone())?;
+                if let serde_json::Value::Object(map) = &mut doc {
+                    config_patch::merge(map, &self.changes_from(&on_disk)?);
+                }
+                serde_yaml::to_string(&doc)?
+            }
+            (Ok(existing), _) => {
+                let mut doc: toml_edit::DocumentMut = existing.parse()?;
+                let on_disk: AppConfig = toml::from_str(&existing)?;
+                config_patch::apply(&mut doc, &self.changes_from(&on_disk)?);
+                doc.to_string()
+            }
+            (Err(e), _) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            (Err(_), Some("json")) => serde_json::to_string_pretty(self)?,
+            (Err(_), Some("yaml")) => serde_yaml::to_string(self)?,
+            (Err(_), _) => toml::to_string_pretty(self)?,
+        };
+        fs::write(&path, content)?;
+        Ok(())
     }
+
+    /// Settings that differ from `on_disk`, as a config_patch patch
+    fn changes_from(
+        &self,
+        on_disk: &AppConfig,
+    ) -> serde_json::Result<serde_json::Map<String, serde_json::Value>> {
+        Ok(config_patch::diff(
+            &serde_json::to_value(on_disk)?,
+            &serde_json::to_value(self)?,
+        ))
+    }
+}
+
+/// Shell command whose stdout is rendered as the document (`--cmd` mode)
+#[derive(Clone)]
+struct CommandSource {
+    command: String,
+    interval: Duration,
+}
+
+/// Command-line options that shape the app once it starts
+struct StartupOptions {
+    no_truncate: bool,
+    /// `--cmd` source to re-run, if any
+    command_source: Option<CommandSource>,
+    ephemeral: bool,
+    /// Serve the daemon socket (off for `--ephemeral` and `--no-daemon`)
+    daemon: bool,
+    /// `--section` resolved to an index into the large-file sections
+    initial_section: Option<usize>,
+    /// Resolved large-file threshold in bytes (0 = never)
+    large_file_threshold: u64,
+    /// `--large-file-threshold`, overriding `large_file_threshold` in config
+    threshold_override: Option<u64>,
+    /// `file.md#anchor` / `file.md:42` target to scroll to once rendered
+    open_target: Option<OpenTarget>,
+    /// Canonical paths of further file arguments, opened as background tabs
+    extra_files: Vec<String>,
+    /// Address to accept remotely pushed content on (`--port`, `remote_listen`)
+    remote_listen: Option<SocketAddr>,
+    /// `--force`: the file arguments open whatever their extension
+    force: bool,
+    /// Directory given instead of a file (directory mode)
+    directory: Option<PathBuf>,
+    /// `--css` stylesheet, overriding `custom_css`
+    custom_css: Option<PathBuf>,
+}
+
+/// Scroll target requested along with a file (`file.md#anchor` or `file.md:42`)
+#[derive(Clone)]
+enum OpenTarget {
+    /// Line to scroll to (0-indexed)
+    Line(usize),
+    /// Heading slug to scroll to
+    Anchor(String),
+}
+
+/// A validated scroll target, handed to the frontend once with the next payload
+#[derive(Clone, serde::Serialize)]
+struct ScrollTarget {
+    /// Target line (0-indexed)
+    line: usize,
+    /// Heading slug, when the target was an anchor
+    anchor: Option<String>,
+    /// Index of the heading at or above the line in the `toc` outline
+    heading_index: Option<usize>,
+    /// Number of lines in the document, for scrolling to lines outside any heading
+    total_lines: usize,
 }
 
 /// Section extracted from markdown for TOC/accordion display
@@ -114,6 +957,10 @@ struct MarkdownSection {
     content: String,
     /// Line number where this section starts (0-indexed)
     start_line: usize,
+    /// Hierarchical heading number (`2.3.1`) when `number_headings` is enabled
+    number: Option<String>,
+    /// GitHub-style slug for `#heading` links (empty for the synthetic intro/document sections)
+    anchor: String,
 }
 
 fn main() {
@@ -134,20 +981,258 @@ fn main() {
         }
     }
 
+    // Print daemon socket diagnostics and exit
+    if args.iter().any(|arg| arg == "--socket-info") {
+        let info = socket_info();
+        match serde_json::to_string_pretty(&info) {
+            Ok(json) => println!("{}", json),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+        process::exit(0);
+    }
+
     // Parse --no-truncate flag
     let no_truncate_flag = args.iter().any(|arg| arg == "--no-truncate");
 
-    // Find file argument (first non-flag argument after program name)
-    let file_arg = args.iter().skip(1).find(|arg| !arg.starts_with("--"));
+    // Parse --ephemeral flag (no config/state writes, no daemon)
+    let ephemeral = args.iter().any(|arg| arg == "--ephemeral");
 
-    // Load config file
+    // Parse --force/--any flag (open files whatever their extension)
+    let force = args.iter().any(|arg| arg == "--force" || arg == "--any");
+
+    // Parse --new-window flag (open in a fresh instance instead of the daemon)
+    let new_window = args.iter().any(|arg| arg == "--new-window");
+
+    // Parse --daemon / --no-daemon, overriding `daemon` in config.toml (the last one wins)
+    let daemon_flag = args.iter().rev().find_map(|arg| match arg.as_str() {
+        "--daemon" => Some(true),
+        "--no-daemon" => Some(false),
+        _ => None,
+    });
+
+    // Parse --stdout-text flag (print plain text and exit)
+    let stdout_text = args.iter().any(|arg| arg == "--stdout-text");
+
+    // Parse --dump-html <path> (write the --print HTML page to a file and exit)
+    let dump_html = flag_value(&args, "--dump-html").map(PathBuf::from);
+
+    // Parse --css <file> (a stylesheet applied over the built-in styles)
+    let custom_css = flag_value(&args, "--css").map(|path| {
+        env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| PathBuf::from(path))
+    });
+
+    // Parse --print[=html|text] (render to stdout and exit, never opening a window)
+    let print_format = args.iter().find_map(|arg| match arg.as_str() {
+        "--print" | "--print=html" => Some(PrintFormat::Html),
+        "--print=text" => Some(PrintFormat::Text),
+        other if other.starts_with("--print=") => {
+            eprintln!("Error: --print expects html or text");
+            process::exit(1);
+        }
+        _ => None,
+    });
+
+    // Load config file
     let config = AppConfig::load();
     let no_truncate = no_truncate_flag || config.no_truncate;
 
+    // Whether this instance talks to the daemon at all. `--new-window` only skips
+    // handing the file over: with no daemon running, the new window still becomes it.
+    let daemon = !ephemeral && daemon_flag.or(config.daemon).unwrap_or(true);
+
+    // Parse --large-file-threshold <size>, overriding config.toml
+    let threshold_override = flag_value(&args, "--large-file-threshold").map(|value| {
+        parse_byte_size(value).unwrap_or_else(|| {
+            eprintln!("Error: --large-file-threshold expects a size like 1048576 or 1MB");
+            process::exit(1);
+        })
+    });
+    let large_file_threshold = threshold_override.unwrap_or_else(|| config.large_file_threshold());
+
+    // Parse --port <port>: accept pushed content on all interfaces, overriding config.toml
+    let remote_listen = match flag_value(&args, "--port") {
+        Some(value) => match value.parse::<u16>() {
+            Ok(port) => Some(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port))),
+            Err(_) => {
+                eprintln!("Error: --port expects a port number");
+                process::exit(1);
+            }
+        },
+        None => config.remote_listen(),
+    };
+
+    // Read markdown from stdin for `glance -`, or when input is piped in without a file
+    let first_arg = positional_args(&args).into_iter().next().cloned();
+    let stdin_content = if first_arg.as_deref() == Some("-") {
+        Some(read_stdin())
+    } else if first_arg.is_none() && flag_value(&args, "--cmd").is_none() {
+        // GUI launches often get a non-terminal but empty stdin, so only use it if it has content
+        read_piped_stdin().filter(|content| !content.trim().is_empty())
+    } else {
+        None
+    };
+
+    // Output modes print or write one document and exit. They never open a window, so
+    // without an input (or with a directory, below) there is nothing to do.
+    let output_flag = [
+        (print_format.is_some(), "--print"),
+        (dump_html.is_some(), "--dump-html"),
+        (stdout_text, "--stdout-text"),
+    ]
+    .into_iter()
+    .find_map(|(set, flag)| set.then_some(flag));
+    if let Some(flag) = output_flag {
+        if stdin_content.is_none() && first_arg.is_none() {
+            eprintln!("Error: {} needs a file or input on stdin", flag);
+            process::exit(1);
+        }
+    }
+
+    // Find file argument (first non-flag argument after program name),
+    // falling back to the last-opened file when configured
+    let file_arg = first_arg.or_else(|| {
+        if config.reopen_last_file {
+            RecentFiles::load().most_recent_existing()
+        } else {
+            None
+        }
+    });
+
+    // Further file arguments open as background tabs
+    let extra_files: Vec<String> = positional_args(&args)
+        .into_iter()
+        .skip(1)
+        .map(|arg| {
+            let path = env::current_dir()
+                .map(|cwd| cwd.join(arg))
+                .unwrap_or_else(|_| PathBuf::from(arg));
+            if !path.exists() {
+                GlanceError::NotFound(path).exit();
+            }
+            if !force && classify_file(&path, &config).is_none() {
+                GlanceError::UnsupportedType(path).exit();
+            }
+            fs::canonicalize(&path)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+
+    // Split a `#anchor` or `:line` scroll target off the file argument
+    let (file_arg, open_target) = match file_arg {
+        Some(arg) => {
+            let (path, target) = split_open_target(&arg);
+            (Some(path), target)
+        }
+        None => (None, None),
+    };
+
+    // A directory argument opens directory mode: a live sidebar of its files
+    let directory = file_arg
+        .as_deref()
+        .map(|arg| {
+            env::current_dir()
+                .map(|cwd| cwd.join(arg))
+                .unwrap_or_else(|_| PathBuf::from(arg))
+        })
+        .filter(|path| path.is_dir())
+        .map(|path| fs::canonicalize(&path).unwrap_or(path));
+    if let (Some(flag), Some(dir)) = (output_flag, &directory) {
+        eprintln!(
+            "Error: {} needs a file, not a directory: {}",
+            flag,
+            dir.display()
+        );
+        process::exit(1);
+    }
+
+    // Command mode: render a shell command's stdout instead of a file
+    if let Some(command) = flag_value(&args, "--cmd") {
+        let interval = match flag_value(&args, "--interval").map(str::parse::<u64>) {
+            None => DEFAULT_COMMAND_INTERVAL_SECS,
+            Some(Ok(secs)) => secs.max(1),
+            Some(Err(_)) => {
+                eprintln!("Error: --interval expects a number of seconds");
+                process::exit(1);
+            }
+        };
+        let source = CommandSource {
+            command: command.to_string(),
+            interval: Duration::from_secs(interval),
+        };
+
+        let content = match run_shell_command(&source.command) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        };
+        let is_large_file =
+            exceeds_threshold(content.len() as u64, large_file_threshold) && !no_truncate;
+
+        run_app(
+            String::new(),
+            source.command.clone(),
+            content,
+            is_large_file,
+            config,
+            StartupOptions {
+                no_truncate,
+                command_source: Some(source),
+                ephemeral,
+                daemon,
+                initial_section: None,
+                large_file_threshold,
+                threshold_override,
+                open_target: None,
+                extra_files: Vec::new(),
+                remote_listen,
+                force: false,
+                directory: None,
+                custom_css,
+            },
+        );
+        return;
+    }
+
     // If a file is provided via CLI, load it; otherwise start with empty state
     // (file can be opened later via drag-drop, Cmd+O, or OS file association)
-    let (file_path, file_name, content, is_large_file) = match file_arg {
-        Some(path) => {
+    let (file_path, file_name, content, is_large_file) = match (stdin_content, file_arg) {
+        (Some(content), _) => {
+            // Piped content has no file: no daemon, no watcher, no recent-files entry
+            if content.trim().is_empty() {
+                eprintln!("Error: No input on stdin");
+                process::exit(1);
+            }
+            if stdout_text {
+                print!("{}", plaintext::to_plain_text(&content));
+                process::exit(0);
+            }
+            if let Some(format) = print_format {
+                print_document(&content, "", "stdin", format, &config);
+            }
+            if let Some(output) = &dump_html {
+                dump_document(&content, "", "stdin", output, &config);
+            }
+
+            let is_large_file =
+                exceeds_threshold(content.len() as u64, large_file_threshold) && !no_truncate;
+            (String::new(), String::from("stdin"), content, is_large_file)
+        }
+        (None, Some(_)) if directory.is_some() => {
+            // Directory mode starts without a document; files open from the sidebar
+            let name = directory
+                .as_deref()
+                .and_then(Path::file_name)
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| "Glance".to_string());
+            (String::new(), name, String::new(), false)
+        }
+        (None, Some(path)) => {
             let file_path = PathBuf::from(path);
 
             // Convert relative path to absolute using current working directory
@@ -161,46 +1246,107 @@ fn main() {
 
             // Check if file exists
             if !file_path.exists() {
-                eprintln!("Error: File not found: {}", file_path.display());
-                process::exit(1);
+                GlanceError::NotFound(file_path).exit();
             }
 
-            // Try to send to running daemon first
-            let absolute_path = fs::canonicalize(&file_path).unwrap_or_else(|_| file_path.clone());
-            if send_to_daemon(absolute_path.to_string_lossy().as_ref()) {
-                // Daemon is running and received the file
-                // On macOS, use open command to bring window to front
-                #[cfg(target_os = "macos")]
-                {
-                    let _ = std::process::Command::new("open")
-                        .arg("-a")
-                        .arg("glance")
-                        .spawn();
-                }
-                // On Linux, the socket server handles window focus
+            // Same extension allowlist the daemon and drag-and-drop apply
+            if !force && classify_file(&file_path, &config).is_none() {
+                GlanceError::UnsupportedType(file_path).exit();
+            }
+
+            // Headless plain-text export: print and exit without starting the app
+            if stdout_text {
+                let content = read_text_file(&file_path).unwrap_or_else(|e| e.exit());
+                print!("{}", plaintext::to_plain_text(&content));
                 process::exit(0);
             }
 
+            if let Some(format) = print_format {
+                let content = read_text_file(&file_path).unwrap_or_else(|e| e.exit());
+                let file_name = file_path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                print_document(
+                    &content,
+                    &file_path.to_string_lossy(),
+                    &file_name,
+                    format,
+                    &config,
+                );
+            }
+
+            if let Some(output) = &dump_html {
+                let content = read_text_file(&file_path).unwrap_or_else(|e| e.exit());
+                let file_name = file_path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                dump_document(
+                    &content,
+                    &file_path.to_string_lossy(),
+                    &file_name,
+                    output,
+                    &config,
+                );
+            }
+
             // Get file size
             let file_size = file_path.metadata().map(|m| m.len()).unwrap_or(0);
 
             // Read file content
-            let content = match fs::read_to_string(&file_path) {
-                Ok(c) => c,
-                Err(e) => {
-                    eprintln!("Error: Failed to read file: {}", e);
-                    process::exit(1);
-                }
-            };
+            let content = read_text_file(&file_path).unwrap_or_else(|e| e.exit());
 
             // Check if file is empty
             if content.trim().is_empty() {
-                eprintln!("Error: File is empty: {}", file_path.display());
-                process::exit(1);
+                GlanceError::Empty(file_path).exit();
+            }
+
+            // Try to send to running daemon first, along with the content we just read
+            let absolute_path = fs::canonicalize(&file_path).unwrap_or_else(|_| file_path.clone());
+            let open_options = OpenOptions {
+                no_truncate: no_truncate_flag,
+                section: flag_value(&args, "--section").map(str::to_string),
+                ..OpenOptions::default()
+            }
+            .with_target(open_target.clone());
+            // `--force` files open in their own instance: the daemon's allowlist is only
+            // ever widened by its own command line, never by a socket client
+            if daemon && !new_window && !force {
+                match send_to_daemon(
+                    absolute_path.to_string_lossy().as_ref(),
+                    Some(&content),
+                    &open_options,
+                ) {
+                    Ok(true) => {
+                        // Daemon is running and opened the file; send the rest as tabs
+                        let mut failed = false;
+                        for path in &extra_files {
+                            match send_to_daemon(path, None, &OpenOptions::default()) {
+                                Ok(true) => {}
+                                Ok(false) => {
+                                    eprintln!("Error: The running instance did not open {}", path);
+                                    failed = true;
+                                }
+                                Err(e) => {
+                                    eprintln!("Error: {}", e);
+                                    failed = true;
+                                }
+                            }
+                        }
+                        // The daemon raises its own window
+                        process::exit(if failed { 1 } else { 0 });
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        process::exit(1);
+                    }
+                }
             }
 
             // Determine if we should use large file mode
-            let is_large_file = file_size > LARGE_FILE_THRESHOLD && !no_truncate;
+            let is_large_file = exceeds_threshold(file_size, large_file_threshold) && !no_truncate;
 
             // Get absolute path and filename for window title
             let absolute_path = fs::canonicalize(&file_path).unwrap_or_else(|_| file_path.clone());
@@ -209,6 +1355,10 @@ fn main() {
                 .map(|n| n.to_string_lossy().to_string())
                 .unwrap_or_else(|| "Glance".to_string());
 
+            if !ephemeral {
+                RecentFiles::record(&absolute_path.to_string_lossy());
+            }
+
             (
                 absolute_path.to_string_lossy().to_string(),
                 file_name,
@@ -216,308 +1366,3075 @@ fn main() {
                 is_large_file,
             )
         }
-        None => {
+        (None, None) => {
             // No file provided - start with empty state
             // File will be opened via OS file association, drag-drop, or Cmd+O
             (String::new(), String::from("Glance"), String::new(), false)
         }
     };
 
+    // Resolve --section against the sections shown in large-file mode
+    let initial_section = flag_value(&args, "--section").and_then(|target| {
+        let index = if is_large_file {
+            find_section(&extract_sections(&content), target)
+        } else {
+            None
+        };
+        if index.is_none() {
+            eprintln!("Warning: Section not found: {}", target);
+        }
+        index
+    });
+
     // Run the Tauri application
-    run_app(file_path, file_name, content, is_large_file, no_truncate);
+    run_app(
+        file_path,
+        file_name,
+        content,
+        is_large_file,
+        config,
+        StartupOptions {
+            no_truncate,
+            command_source: None,
+            ephemeral,
+            daemon,
+            initial_section,
+            large_file_threshold,
+            threshold_override,
+            open_target,
+            extra_files,
+            remote_listen,
+            force,
+            directory,
+            custom_css,
+        },
+    );
 }
 
-fn print_help() {
-    println!("glance - A minimal markdown viewer");
-    println!();
-    println!("USAGE:");
-    println!("    glance <file.md> [options]");
-    println!();
-    println!("OPTIONS:");
-    println!("    --help, -h       Show this help message");
-    println!("    --version, -v    Show version");
-    println!("    --no-truncate    Render entire file regardless of size");
+/// Find a section by anchor slug, then by title (exact, then substring; case-insensitive)
+fn find_section(sections: &[MarkdownSection], target: &str) -> Option<usize> {
+    let target = target.trim().trim_start_matches('#').to_lowercase();
+    let titles: Vec<String> = sections
+        .iter()
+        .map(|section| plaintext::strip_inline(&section.title))
+        .collect();
+
+    sections
+        .iter()
+        .position(|section| !section.anchor.is_empty() && section.anchor == target)
+        .or_else(|| titles.iter().position(|t| t.to_lowercase() == target))
+        .or_else(|| {
+            titles
+                .iter()
+                .position(|t| t.to_lowercase().contains(&target))
+        })
 }
 
-/// Try to send a file path to the running daemon
-/// Returns true if successful (daemon is running), false otherwise
-fn send_to_daemon(file_path: &str) -> bool {
-    if let Some(socket_path) = get_socket_path() {
-        if let Ok(mut stream) = UnixStream::connect(&socket_path) {
-            if let Ok(_) = stream.write_all(file_path.as_bytes()) {
-                return true;
+/// Split a trailing `#anchor` or `:line` (1-based) target off a file argument. A path
+/// that exists as given is never split, so files with `#` or `:` in their name still open.
+fn split_open_target(arg: &str) -> (String, Option<OpenTarget>) {
+    if Path::new(arg).exists() {
+        return (arg.to_string(), None);
+    }
+    if let Some((path, anchor)) = arg.rsplit_once('#') {
+        if !path.is_empty() && !anchor.is_empty() {
+            return (
+                path.to_string(),
+                Some(OpenTarget::Anchor(anchor.to_string())),
+            );
+        }
+    }
+    if let Some((path, line)) = arg.rsplit_once(':') {
+        if let Ok(line) = line.parse::<usize>() {
+            if !path.is_empty() && line > 0 {
+                return (path.to_string(), Some(OpenTarget::Line(line - 1)));
             }
         }
     }
-    false
+    (arg.to_string(), None)
 }
 
-/// Start a Unix socket server that listens for file paths from other glance instances
-fn start_socket_server(state: Arc<AppState>, app_handle: tauri::AppHandle) {
-    if let Some(socket_path) = get_socket_path() {
-        // Remove old socket file if it exists
-        let _ = fs::remove_file(&socket_path);
-
-        // Create parent directories if needed
-        if let Some(parent) = socket_path.parent() {
-            let _ = fs::create_dir_all(parent);
+/// Check a requested target against the document: the line must exist and the anchor
+/// must match a heading slug. Invalid targets are dropped with a warning.
+fn resolve_scroll_target(
+    target: OpenTarget,
+    content: &str,
+    toc: &[OutlineEntry],
+) -> Option<ScrollTarget> {
+    let total_lines = content.lines().count();
+    let (line, anchor) = match target {
+        OpenTarget::Line(line) if line < total_lines => (line, None),
+        OpenTarget::Line(line) => {
+            eprintln!(
+                "Warning: Line {} is past the end of the file ({} lines)",
+                line + 1,
+                total_lines
+            );
+            return None;
         }
+        OpenTarget::Anchor(anchor) => {
+            let anchor = anchor.to_lowercase();
+            match heading_slugs(toc).iter().position(|slug| *slug == anchor) {
+                Some(index) => (toc[index].line, Some(anchor)),
+                None => {
+                    eprintln!("Warning: No heading with anchor #{}", anchor);
+                    return None;
+                }
+            }
+        }
+    };
 
-        thread::spawn(move || {
-            if let Ok(listener) = UnixListener::bind(&socket_path) {
-                for stream in listener.incoming() {
-                    if let Ok(mut stream) = stream {
-                        let state = state.clone();
-                        let app_handle = app_handle.clone();
-
-                        // Read file path from socket
-                        let mut buffer = [0u8; 4096];
-                        if let Ok(n) = stream.read(&mut buffer) {
-                            let file_path_str = String::from_utf8_lossy(&buffer[..n]).to_string();
-                            let file_path = PathBuf::from(&file_path_str);
-
-                            // Security: Validate file exists
-                            if !file_path.exists() {
-                                eprintln!("Socket: File not found: {}", file_path.display());
-                                continue;
-                            }
-
-                            // Security: Validate it's a markdown file (prevent arbitrary file access)
-                            let extension = file_path
-                                .extension()
-                                .map(|e| e.to_string_lossy().to_lowercase());
-                            if extension.as_deref() != Some("md")
-                                && extension.as_deref() != Some("markdown")
-                                && extension.as_deref() != Some("puml")
-                                && extension.as_deref() != Some("plantuml")
-                            {
-                                eprintln!(
-                                    "Socket: Invalid file type (only .md/.markdown/.puml/.plantuml allowed): {}",
-                                    file_path.display()
-                                );
-                                continue;
-                            }
+    Some(ScrollTarget {
+        line,
+        anchor,
+        heading_index: toc.iter().rposition(|entry| entry.line <= line),
+        total_lines,
+    })
+}
 
-                            // Security: Canonicalize path to prevent path traversal
-                            let file_path = match fs::canonicalize(&file_path) {
-                                Ok(p) => p,
-                                Err(e) => {
-                                    eprintln!("Socket: Failed to canonicalize path: {}", e);
-                                    continue;
-                                }
-                            };
+/// Get the value following a flag (e.g. `--cmd "ls"`)
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
 
-                            // Read file content
-                            if let Ok(new_content) = fs::read_to_string(&file_path) {
-                                if new_content.trim().is_empty() {
-                                    continue;
-                                }
+/// Non-flag arguments after the program name, skipping values consumed by flags
+fn positional_args(args: &[String]) -> Vec<&String> {
+    let mut positional = Vec::new();
+    let mut skip_next = false;
+    for arg in args.iter().skip(1) {
+        if skip_next {
+            skip_next = false;
+            continue;
+        }
+        if VALUE_FLAGS.contains(&arg.as_str()) {
+            skip_next = true;
+            continue;
+        }
+        if !arg.starts_with("--") {
+            positional.push(arg);
+        }
+    }
+    positional
+}
 
-                                // Get file metadata
-                                let file_size = file_path.metadata().map(|m| m.len()).unwrap_or(0);
-                                let no_truncate =
-                                    *state.no_truncate.lock().unwrap_or_else(|e| e.into_inner());
-                                let is_large_file =
-                                    file_size > LARGE_FILE_THRESHOLD && !no_truncate;
-
-                                let new_file_name = file_path
-                                    .file_name()
-                                    .map(|n| n.to_string_lossy().to_string())
-                                    .unwrap_or_else(|| "Glance".to_string());
-
-                                // Update state (handle poisoned locks gracefully)
-                                {
-                                    let mut content =
-                                        state.content.lock().unwrap_or_else(|e| e.into_inner());
-                                    *content = new_content;
-                                }
-                                {
-                                    let mut fp =
-                                        state.file_path.lock().unwrap_or_else(|e| e.into_inner());
-                                    *fp = file_path.to_string_lossy().to_string();
-                                }
-                                {
-                                    let mut fn_state =
-                                        state.file_name.lock().unwrap_or_else(|e| e.into_inner());
-                                    *fn_state = new_file_name.clone();
-                                }
-                                {
-                                    let mut lf = state
-                                        .is_large_file
-                                        .lock()
-                                        .unwrap_or_else(|e| e.into_inner());
-                                    *lf = is_large_file;
-                                }
+/// Parse a size like "1048576", "500KB", "1.5 MB" or "2GiB" (binary units)
+fn parse_byte_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    Some((number * multiplier as f64) as u64)
+}
 
-                                // Emit event to frontend and show window
-                                if let Some(window) = app_handle.get_webview_window("main") {
-                                    let window_title = format!("{} - Glance", new_file_name);
-                                    if let Err(e) = window.set_title(&window_title) {
-                                        eprintln!("Failed to set window title: {}", e);
-                                    }
-                                    // Make sure window is visible
-                                    if let Err(e) = window.show() {
-                                        eprintln!("Failed to show window: {}", e);
-                                    }
-                                    if let Err(e) = window.set_focus() {
-                                        eprintln!("Failed to focus window: {}", e);
-                                    }
-                                    if let Err(e) = window.emit("file-loaded", ()) {
-                                        eprintln!("Failed to emit file-loaded event: {}", e);
-                                    }
-                                }
+/// Whether content of `size` bytes opens in large-file mode (a threshold of 0 disables it)
+fn exceeds_threshold(size: u64, threshold: u64) -> bool {
+    threshold > 0 && size > threshold
+}
 
-                                // Tell watcher about new file
-                                if let Some(ref sender) = *state
-                                    .watcher_control
-                                    .lock()
-                                    .unwrap_or_else(|e| e.into_inner())
-                                {
-                                    let _ = sender.send(file_path);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        });
-    }
+/// Errors that end a launch on a file argument, each with its own exit code so
+/// wrapping scripts can tell them apart
+enum GlanceError {
+    /// The file does not exist (exit code 2)
+    NotFound(PathBuf),
+    /// The file exists but could not be read (exit code 3)
+    ReadFailed(std::io::Error),
+    /// The file is empty or whitespace-only (exit code 4)
+    Empty(PathBuf),
+    /// The file's extension is not one glance opens (exit code 5)
+    UnsupportedType(PathBuf),
+    /// The file is binary or not valid UTF-8 (exit code 5)
+    NotText(PathBuf),
 }
 
-#[tauri::command]
-fn get_markdown_content(state: tauri::State<AppState>) -> MarkdownContent {
-    // Use unwrap_or_else to handle poisoned locks gracefully
-    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
-    let file_path = state.file_path.lock().unwrap_or_else(|e| e.into_inner());
-    let file_name = state.file_name.lock().unwrap_or_else(|e| e.into_inner());
-    let is_large_file = *state
-        .is_large_file
-        .lock()
-        .unwrap_or_else(|e| e.into_inner());
+impl GlanceError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            GlanceError::NotFound(_) => 2,
+            GlanceError::ReadFailed(_) => 3,
+            GlanceError::Empty(_) => 4,
+            GlanceError::UnsupportedType(_) | GlanceError::NotText(_) => 5,
+        }
+    }
 
-    // Get directory of the markdown file for resolving relative image paths
-    let file_dir = PathBuf::from(file_path.as_str())
-        .parent()
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_default();
+    /// Print the error to stderr and exit with its code
+    fn exit(self) -> ! {
+        eprintln!("Error: {}", self);
+        process::exit(self.exit_code());
+    }
+}
 
-    // Extract sections if in large file mode
-    let sections = if is_large_file {
-        extract_sections(&content)
-    } else {
-        Vec::new()
-    };
+impl std::fmt::Display for GlanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GlanceError::NotFound(path) => write!(f, "File not found: {}", path.display()),
+            GlanceError::ReadFailed(e) => write!(f, "Failed to read file: {}", e),
+            GlanceError::Empty(path) => write!(f, "File is empty: {}", path.display()),
+            GlanceError::UnsupportedType(path) => {
+                write!(f, "Unsupported file type: {}", path.display())
+            }
+            GlanceError::NotText(path) => {
+                write!(f, "File is not valid UTF-8 text: {}", path.display())
+            }
+        }
+    }
+}
 
-    // Load extensions config
-    let config = AppConfig::load();
+/// Read a file argument as text, rejecting binary and non-UTF-8 files
+fn read_text_file(path: &Path) -> Result<String, GlanceError> {
+    file_types::read_text(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::InvalidData {
+            GlanceError::NotText(path.to_path_buf())
+        } else {
+            GlanceError::ReadFailed(e)
+        }
+    })
+}
 
-    // Check if this is a PlantUML file
-    let is_plantuml_file = PathBuf::from(file_path.as_str())
-        .extension()
-        .map(|e| {
-            let ext = e.to_string_lossy().to_lowercase();
-            ext == "puml" || ext == "plantuml"
-        })
-        .unwrap_or(false);
+/// Read a file to display in the running app, with a message fit for the frontend
+fn read_document(path: &Path) -> Result<String, String> {
+    read_text_file(path).map_err(|e| e.to_string())
+}
 
-    MarkdownContent {
-        content: content.clone(),
-        file_path: file_path.clone(),
-        file_name: file_name.clone(),
-        file_dir,
-        is_large_file,
-        sections,
-        extensions: config.extensions,
-        is_plantuml_file,
+/// Read all of stdin, exiting with an error if it cannot be read as UTF-8
+fn read_stdin() -> String {
+    if std::io::stdin().is_terminal() {
+        return String::new();
     }
+    let mut content = String::new();
+    if let Err(e) = std::io::stdin().read_to_string(&mut content) {
+        eprintln!("Error: Failed to read stdin: {}", e);
+        process::exit(1);
+    }
+    content
 }
 
-#[tauri::command]
-fn open_dropped_file(
-    path: String,
-    state: tauri::State<AppState>,
-    window: tauri::WebviewWindow,
-) -> Result<String, String> {
-    let file_path = PathBuf::from(&path);
-
-    // Check if file exists
-    if !file_path.exists() {
-        return Err(format!("File not found: {}", file_path.display()));
+/// Read stdin when it is a pipe or a redirected file and input starts within
+/// STDIN_START_TIMEOUT; None otherwise. Terminals, /dev/null, and sockets or pipes a
+/// launcher left open but never writes to aren't input.
+fn read_piped_stdin() -> Option<String> {
+    if !stdin_is_piped() {
+        return None;
     }
 
-    // Check if it's a markdown file
-    let extension = file_path
-        .extension()
-        .map(|e| e.to_string_lossy().to_lowercase());
-    if extension.as_deref() != Some("md")
-        && extension.as_deref() != Some("markdown")
-        && extension.as_deref() != Some("puml")
-        && extension.as_deref() != Some("plantuml")
+    let (started_tx, started_rx) = channel();
+    let (done_tx, done_rx) = channel();
+    thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        let mut bytes = Vec::new();
+        // Wait for the first byte (or end of input) before committing to read it all
+        let first = stdin.by_ref().take(1).read_to_end(&mut bytes);
+        let _ = started_tx.send(());
+        let result = first
+            .and_then(|_| stdin.read_to_end(&mut bytes))
+            .map(|_| bytes);
+        let _ = done_tx.send(result);
+    });
+
+    if let Err(std::sync::mpsc::RecvTimeoutError::Timeout) =
+        started_rx.recv_timeout(STDIN_START_TIMEOUT)
     {
-        return Err("Only markdown and PlantUML files are supported".to_string());
+        // Left blocked on the idle pipe; it ends with the process
+        return None;
+    }
+    match done_rx.recv() {
+        Ok(Ok(bytes)) => Some(String::from_utf8(bytes).unwrap_or_else(|_| {
+            eprintln!("Error: Failed to read stdin: input is not valid UTF-8");
+            process::exit(1);
+        })),
+        Ok(Err(e)) => {
+            eprintln!("Error: Failed to read stdin: {}", e);
+            process::exit(1);
+        }
+        Err(_) => None,
     }
+}
 
-    // Get file size
-    let file_size = file_path.metadata().map(|m| m.len()).unwrap_or(0);
+/// Whether stdin is a pipe or a regular file, rather than a terminal, a device such as
+/// /dev/null, or a socket
+#[cfg(unix)]
+fn stdin_is_piped() -> bool {
+    use std::os::fd::AsFd;
+    use std::os::unix::fs::FileTypeExt;
 
-    // Read file content
-    let new_content =
-        fs::read_to_string(&file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    std::io::stdin()
+        .as_fd()
+        .try_clone_to_owned()
+        .map(fs::File::from)
+        .and_then(|file| file.metadata())
+        .is_ok_and(|metadata| metadata.file_type().is_fifo() || metadata.is_file())
+}
 
-    if new_content.trim().is_empty() {
-        return Err(format!("File is empty: {}", file_path.display()));
-    }
+#[cfg(not(unix))]
+fn stdin_is_piped() -> bool {
+    !std::io::stdin().is_terminal()
+}
 
-    // Get absolute path and filename
-    let absolute_path = fs::canonicalize(&file_path).unwrap_or_else(|_| file_path.clone());
-    let new_file_name = file_path
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| "Glance".to_string());
+/// Output of `--print`
+#[derive(Clone, Copy)]
+enum PrintFormat {
+    /// A standalone HTML page, as export_html writes it
+    Html,
+    /// Plain text with markdown syntax stripped
+    Text,
+}
 
-    // Check if no_truncate is set
-    let no_truncate = *state.no_truncate.lock().unwrap_or_else(|e| e.into_inner());
-    let is_large_file = file_size > LARGE_FILE_THRESHOLD && !no_truncate;
+/// Render a document to stdout and exit, for `--print`. The whole document is always
+/// rendered: headless output is never truncated.
+fn print_document(
+    content: &str,
+    file_path: &str,
+    file_name: &str,
+    format: PrintFormat,
+    config: &AppConfig,
+) -> ! {
+    let output = match format {
+        PrintFormat::Html => standalone_html(content, file_path, file_name, config),
+        PrintFormat::Text => plaintext::to_plain_text(split_front_matter(content).1),
+    };
+    write_stdout_and_exit(&output);
+}
 
-    // Update state (handle poisoned locks gracefully)
-    {
-        let mut content = state.content.lock().unwrap_or_else(|e| e.into_inner());
-        *content = new_content;
-    }
-    {
-        let mut file_path_state = state.file_path.lock().unwrap_or_else(|e| e.into_inner());
-        *file_path_state = absolute_path.to_string_lossy().to_string();
+/// Write the standalone HTML page `--print` prints to `output` instead, and exit, for
+/// `--dump-html`. The file is opened before rendering, so an unwritable path fails
+/// without the work.
+fn dump_document(
+    content: &str,
+    file_path: &str,
+    file_name: &str,
+    output: &Path,
+    config: &AppConfig,
+) -> ! {
+    let written = fs::File::create(output).and_then(|mut file| {
+        let html = standalone_html(content, file_path, file_name, config);
+        file.write_all(html.as_bytes())
+    });
+    if let Err(e) = written {
+        eprintln!("Error: Failed to write {}: {}", output.display(), e);
+        process::exit(1);
     }
+    process::exit(0);
+}
+
+/// Write headless output to stdout and exit
+fn write_stdout_and_exit(output: &str) -> ! {
+    let mut stdout = std::io::stdout().lock();
+    if let Err(e) = stdout
+        .write_all(output.as_bytes())
+        .and_then(|_| stdout.flush())
     {
-        let mut file_name_state = state.file_name.lock().unwrap_or_else(|e| e.into_inner());
-        *file_name_state = new_file_name.clone();
+        // A closed pipe (`glance --print file.md | head`) isn't an error
+        if e.kind() != std::io::ErrorKind::BrokenPipe {
+            eprintln!("Error: Failed to write output: {}", e);
+            process::exit(1);
+        }
     }
-    {
-        let mut large_file_state = state
-            .is_large_file
+    process::exit(0);
+}
+
+/// Run a shell command and return its stdout (capped at MAX_COMMAND_OUTPUT)
+fn run_shell_command(command: &str) -> Result<String, String> {
+    #[cfg(windows)]
+    let output = process::Command::new("cmd").args(["/C", command]).output();
+    #[cfg(not(windows))]
+    let output = process::Command::new("sh").arg("-c").arg(command).output();
+
+    let mut stdout = output
+        .map_err(|e| format!("Failed to run command: {}", e))?
+        .stdout;
+    stdout.truncate(MAX_COMMAND_OUTPUT);
+    Ok(String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Re-run a `--cmd` source on its interval, emitting `file-changed` when the output changes.
+/// Stops once a real file is opened in the window.
+fn start_command_refresh(source: CommandSource, state: AppState, app_handle: tauri::AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(source.interval);
+
+        if !state
+            .file_path
             .lock()
-            .unwrap_or_else(|e| e.into_inner());
-        *large_file_state = is_large_file;
-    }
+            .unwrap_or_else(|e| e.into_inner())
+            .is_empty()
+        {
+            return;
+        }
 
-    // Update window title
-    let window_title = format!("{} - Glance", new_file_name);
-    let _ = window.set_title(&window_title);
+        let new_content = match run_shell_command(&source.command) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
 
-    // Tell watcher thread to watch new file
-    if let Some(ref sender) = *state
-        .watcher_control
-        .lock()
-        .unwrap_or_else(|e| e.into_inner())
-    {
-        let _ = sender.send(absolute_path);
-    }
+        {
+            let mut content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+            if *content == new_content {
+                continue;
+            }
+            let no_truncate = *state.no_truncate.lock().unwrap_or_else(|e| e.into_inner());
+            *state
+                .is_large_file
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()) =
+                exceeds_threshold(new_content.len() as u64, state.large_file_threshold())
+                    && !no_truncate;
+            *content = new_content;
+        }
 
-    Ok(new_file_name)
+        if let Some(window) = app_handle.get_webview_window("main") {
+            let _ = window.emit("file-changed", ());
+        }
+    });
 }
 
-#[derive(Clone, serde::Serialize)]
-struct MarkdownContent {
+fn print_help() {
+    println!("glance - A minimal markdown viewer");
+    println!();
+    println!("USAGE:");
+    println!("    glance <file.md>... [options]");
+    println!("    glance <directory> [options]");
+    println!("    <command> | glance [-] [options]");
+    println!();
+    println!("OPTIONS:");
+    println!("    --help, -h       Show this help message");
+    println!("    --version, -v    Show version");
+    println!("    --no-truncate    Render entire file regardless of size");
+    println!(
+        "    --large-file-threshold <size> Sectioned view above this size, e.g. 1MB (0 = never)"
+    );
+    println!("    --stdout-text    Print the file as plain text and exit");
+    println!("    --css <file>     Apply a custom stylesheet over the built-in styles");
+    println!("    --print[=html|text] Render the file to stdout (HTML by default) and exit");
+    println!("    --dump-html <path> Write the --print HTML page to <path> instead and exit");
+    println!("    --section <name> Open a large file at the section with this title or anchor");
+    println!("    --socket-info    Print the daemon socket path and status and exit");
+    println!("    --ephemeral      Write no config or state and skip the daemon");
+    println!("    --new-window     Open in a new window instead of the running instance");
+    println!("    --daemon         Use the running instance, or become it (default)");
+    println!("    --no-daemon      Run standalone: never use or become the running instance");
+    println!("    --force, --any   Open files whatever their extension (in a new window)");
+    println!("    --cmd <command>  Render a shell command's output, re-running it periodically");
+    println!("    --interval <s>   Seconds between --cmd re-runs (default: 2)");
+    println!("    --port <port>    Accept markdown pushed over TCP (unauthenticated)");
+    println!();
+    println!("EXIT CODES:");
+    println!("    1  Invalid arguments or other errors");
+    println!("    2  File not found");
+    println!("    3  File could not be read");
+    println!("    4  File is empty");
+    println!("    5  Unsupported file type, or not UTF-8 text");
+}
+
+/// Try to hand a file to the running daemon, with the already-read content if given.
+/// Returns Ok(false) if no daemon is running, Ok(true) once the daemon has opened the
+/// file, and the daemon's error if it refused it.
+fn send_to_daemon(
+    file_path: &str,
+    content: Option<&str>,
+    options: &OpenOptions,
+) -> Result<bool, String> {
+    // Overrides need a JSON request; otherwise the content goes in a frame
+    let message = match content {
+        Some(content) if options.is_default() => encode_frame(file_path, content),
+        _ => {
+            let request =
+                serde_json::json!({ "op": "open", "path": file_path, "options": options });
+            format!("{}\n", request).into_bytes()
+        }
+    };
+
+    let Ok(mut stream) = ipc::default_transport().connect() else {
+        return Ok(false);
+    };
+    if stream.write_all(&message).is_err() {
+        return Ok(false);
+    }
+    let received = match read_message_head(&mut stream) {
+        Ok(received) => received,
+        // A daemon that doesn't answer in time is treated as gone
+        Err(e) if ipc::is_timeout(&e) => {
+            eprintln!("The running instance did not reply in time");
+            return Ok(false);
+        }
+        Err(e) => return Err(format!("Failed to read daemon reply: {}", e)),
+    };
+    parse_daemon_reply(&received).map(|()| true)
+}
+
+/// Parse the daemon's reply to a request and turn a refusal into an error
+fn parse_daemon_reply(received: &[u8]) -> Result<(), String> {
+    let line = received.split(|b| *b == b'\n').next().unwrap_or_default();
+    // Daemons that predate replies just close the connection
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return Ok(());
+    }
+
+    let reply: DaemonReply =
+        serde_json::from_slice(line).map_err(|e| format!("Invalid daemon reply: {}", e))?;
+    if reply.ok {
+        Ok(())
+    } else {
+        Err(reply
+            .error
+            .unwrap_or_else(|| "The running instance refused the file".to_string()))
+    }
+}
+
+/// Encode a framed open message: `GLANCE-FRAME <content bytes>\n<path>\n<content>`
+fn encode_frame(file_path: &str, content: &str) -> Vec<u8> {
+    let mut frame = format!("{}{}\n{}\n", FRAME_MAGIC, content.len(), file_path).into_bytes();
+    frame.extend_from_slice(content.as_bytes());
+    frame
+}
+
+/// Parse a framed open message whose first bytes were already `received`, reading the
+/// rest from `stream`. Returns the path and the sender's content, which may be at most
+/// `max_content` bytes.
+fn read_frame(
+    mut received: Vec<u8>,
+    stream: &mut impl Read,
+    max_content: usize,
+) -> Result<(String, String), String> {
+    // Read until both the header line and the path line are complete
+    let mut chunk = [0u8; 4096];
+    let (header_end, path_end) = loop {
+        let mut newlines = received
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| **b == b'\n')
+            .map(|(i, _)| i);
+        if let (Some(header_end), Some(path_end)) = (newlines.next(), newlines.next()) {
+            break (header_end, path_end);
+        }
+        if received.len() > MAX_FRAME_HEADER {
+            return Err("Frame header too long".to_string());
+        }
+        let n = stream
+            .read(&mut chunk)
+            .map_err(|e| format!("Failed to read frame: {}", e))?;
+        if n == 0 {
+            return Err("Truncated frame header".to_string());
+        }
+        received.extend_from_slice(&chunk[..n]);
+    };
+
+    let len = std::str::from_utf8(&received[FRAME_MAGIC.len()..header_end])
+        .ok()
+        .and_then(|s| s.trim().parse::<usize>().ok())
+        .ok_or_else(|| "Invalid frame header".to_string())?;
+    if len > max_content {
+        return Err(format!("Framed content too large: {} bytes", len));
+    }
+
+    let path = String::from_utf8(received[header_end + 1..path_end].to_vec())
+        .map_err(|_| "Frame path is not valid UTF-8".to_string())?;
+
+    let mut content = received.split_off(path_end + 1);
+    if content.len() > len {
+        return Err("Frame content longer than declared".to_string());
+    }
+    // Read as the content arrives rather than allocating the declared length up front,
+    // so a header alone can't claim the whole limit
+    let remaining = (len - content.len()) as u64;
+    stream
+        .take(remaining)
+        .read_to_end(&mut content)
+        .map_err(|e| format!("Failed to read frame: {}", e))?;
+    if content.len() < len {
+        return Err("Truncated frame content".to_string());
+    }
+
+    let content =
+        String::from_utf8(content).map_err(|_| "Framed content is not valid UTF-8".to_string())?;
+    Ok((path, content))
+}
+
+/// Transport-level details about the daemon socket
+#[derive(Clone, serde::Serialize)]
+struct SocketInfo {
+    /// Endpoint in use: socket path (runtime dir, or the cache dir fallback),
+    /// named pipe, or localhost address
+    path: Option<String>,
+    /// Whether the endpoint exists (the socket file, for Unix sockets)
+    exists: bool,
+    /// Whether a daemon accepts connections on it
+    is_live: bool,
+}
+
+/// Report the effective daemon endpoint and whether a daemon is listening on it
+#[tauri::command]
+fn socket_info() -> SocketInfo {
+    let transport = ipc::default_transport();
+    SocketInfo {
+        path: Some(transport.endpoint()),
+        exists: transport.endpoint_exists(),
+        is_live: transport.connect().is_ok(),
+    }
+}
+
+/// Structured request sent to the daemon socket as one line of JSON, e.g.
+/// `{"op":"open","path":"/notes/todo.md"}`. Plain paths and frames are still accepted.
+#[derive(serde::Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum DaemonRequest {
+    /// Open a file, optionally with per-open overrides
+    Open {
+        path: String,
+        #[serde(default)]
+        options: OpenOptions,
+    },
+    /// Open an entry from the recent-files list by index
+    OpenRecent { index: usize },
+}
+
+/// The daemon's answer to any request: `{"ok":true}` or `{"ok":false,"error":"..."}`,
+/// plus the request's `id` if it had one
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DaemonReply {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<serde_json::Value>,
+    ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl DaemonReply {
+    fn from_result(id: Option<serde_json::Value>, result: Result<(), String>) -> Self {
+        match result {
+            Ok(()) => Self {
+                id,
+                ok: true,
+                error: None,
+            },
+            Err(e) => Self {
+                id,
+                ok: false,
+                error: Some(e),
+            },
+        }
+    }
+}
+
+/// Per-open overrides passed through the daemon protocol. They apply to that one load
+/// only; the daemon's own settings (config.toml, its `--no-truncate`) stay sticky and
+/// are used again for the next open.
+#[derive(Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+struct OpenOptions {
+    /// Render the whole file even if it is large
+    #[serde(default)]
+    no_truncate: bool,
+    /// Line to scroll to (0-indexed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    /// Heading slug to scroll to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    anchor: Option<String>,
+    /// Section title or anchor to expand and scroll to in large-file mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    section: Option<String>,
+}
+
+impl OpenOptions {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Set `line` or `anchor` from a parsed `file.md:42` / `file.md#anchor` target
+    fn with_target(mut self, target: Option<OpenTarget>) -> Self {
+        match target {
+            Some(OpenTarget::Line(line)) => self.line = Some(line),
+            Some(OpenTarget::Anchor(anchor)) => self.anchor = Some(anchor),
+            None => {}
+        }
+        self
+    }
+}
+
+/// Start the daemon server that listens for file paths from other glance instances,
+/// plus the remote content listener if one is configured. Does nothing when another
+/// instance (e.g. under `--new-window`) already serves it.
+fn start_socket_server(
+    state: Arc<AppState>,
+    app_handle: tauri::AppHandle,
+    remote_listen: Option<SocketAddr>,
+) {
+    let transport = ipc::default_transport();
+    if transport.connect().is_ok() {
+        eprintln!(
+            "Daemon already running at {}, not listening",
+            transport.endpoint()
+        );
+        return;
+    }
+
+    let listener = match transport.listen() {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to start daemon listener: {}", e);
+            return;
+        }
+    };
+    *state
+        .serves_daemon
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = true;
+
+    if let Some(addr) = remote_listen {
+        start_remote_listener(addr, state.clone(), app_handle.clone());
+    }
+
+    thread::spawn(move || loop {
+        let Ok(mut stream) = listener.accept() else {
+            continue;
+        };
+        // Each client gets its own thread, so one that stalls mid-message (until the
+        // read timeout, or indefinitely on a named pipe) can't hold up the others
+        let state = state.clone();
+        let app_handle = app_handle.clone();
+        thread::spawn(move || {
+            if let Some(reply) = handle_connection(&mut stream, &state, &app_handle) {
+                if let Some(error) = &reply.error {
+                    emit_load_error(&app_handle, error);
+                }
+                write_reply(&mut stream, &reply);
+            }
+        });
+    });
+}
+
+/// Tell the frontend a file sent to it couldn't be shown, so the failure isn't silent
+fn emit_load_error(app_handle: &tauri::AppHandle, message: &str) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        if let Err(e) = window.emit("load-error", message) {
+            eprintln!("Failed to emit load-error event: {}", e);
+        }
+    }
+}
+
+/// Send a reply line to the client, logging refusals on this side too
+fn write_reply(stream: &mut impl Write, reply: &DaemonReply) {
+    if let Some(error) = &reply.error {
+        eprintln!("Socket: {}", error);
+    }
+    match serde_json::to_string(reply) {
+        Ok(line) => {
+            let _ = stream.write_all(format!("{}\n", line).as_bytes());
+        }
+        Err(e) => eprintln!("Socket: Failed to encode reply: {}", e),
+    }
+}
+
+/// Accept markdown pushed from other machines over TCP. Only content frames are
+/// accepted: the frame's path just names the document and is never read, watched, or
+/// recorded, so a remote client can't make this machine open its own files. See
+/// `remote_listen` in the config for the security tradeoff.
+///
+/// A client needs nothing but a shell:
+/// `{ printf 'GLANCE-FRAME %d\nnotes.md\n' "$(wc -c < notes.md)"; cat notes.md; } | nc host 47292`
+fn start_remote_listener(addr: SocketAddr, state: Arc<AppState>, app_handle: tauri::AppHandle) {
+    let listener = match TcpListener::bind(addr) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to listen for remote content on {}: {}", addr, e);
+            return;
+        }
+    };
+    eprintln!("Accepting remote content on {}", addr);
+
+    let active_clients = Arc::new(AtomicUsize::new(0));
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            // A stalled client must not block everyone behind it: it times out, and
+            // meanwhile others are served on their own threads, up to a fixed number
+            let Some(slot) = RemoteClientSlot::acquire(&active_clients) else {
+                write_reply(
+                    &mut stream,
+                    &DaemonReply::from_result(None, Err("Too many remote clients".to_string())),
+                );
+                continue;
+            };
+            let _ = stream.set_read_timeout(Some(REMOTE_READ_TIMEOUT));
+            let state = state.clone();
+            let app_handle = app_handle.clone();
+            thread::spawn(move || {
+                let _slot = slot;
+                handle_remote_connection(&mut stream, &state, &app_handle)
+            });
+        }
+    });
+}
+
+/// One of the MAX_REMOTE_CLIENTS connections served at once, released on drop
+struct RemoteClientSlot(Arc<AtomicUsize>);
+
+impl RemoteClientSlot {
+    fn acquire(active: &Arc<AtomicUsize>) -> Option<Self> {
+        active
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < MAX_REMOTE_CLIENTS).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| RemoteClientSlot(active.clone()))
+    }
+}
+
+impl Drop for RemoteClientSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Read one content frame from a remote client, show it, and reply
+fn handle_remote_connection(
+    stream: &mut TcpStream,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+) {
+    let received = match read_message_head(stream) {
+        Ok(received) => received,
+        Err(e) => {
+            eprintln!("Remote: Failed to read request: {}", e);
+            return;
+        }
+    };
+    if received.iter().all(u8::is_ascii_whitespace) {
+        return;
+    }
+    let result = if received.starts_with(FRAME_MAGIC.as_bytes()) {
+        read_frame(received, stream, MAX_REMOTE_CONTENT)
+            .and_then(|(name, content)| show_remote_content(&name, content, state, app_handle))
+    } else {
+        Err("Remote clients may only send content frames".to_string())
+    };
+    write_reply(stream, &DaemonReply::from_result(None, result));
+}
+
+/// Show pushed content as a document with no file behind it, like piped stdin.
+/// Only the last component of `name` is used, as the window title.
+fn show_remote_content(
+    name: &str,
+    content: String,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+) -> Result<(), String> {
+    if content.trim().is_empty() {
+        return Err("Content is empty".to_string());
+    }
+    let file_name = Path::new(name)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "remote".to_string());
+    let no_truncate = *state.no_truncate.lock().unwrap_or_else(|e| e.into_inner());
+    let is_large_file =
+        exceeds_threshold(content.len() as u64, state.large_file_threshold()) && !no_truncate;
+
+    // Tabs hold local paths, so the pushed document replaces them
+    reset_document_state(state);
+    *state.content.lock().unwrap_or_else(|e| e.into_inner()) = content;
+    *state.file_name.lock().unwrap_or_else(|e| e.into_inner()) = file_name.clone();
+    *state
+        .is_large_file
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = is_large_file;
+    state.mark_active();
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        if let Err(e) = window.set_title(&format!("{} - Glance", file_name)) {
+            eprintln!("Failed to set window title: {}", e);
+        }
+    }
+    emit_tabs_changed(app_handle);
+    show_main_window(app_handle);
+    Ok(())
+}
+
+/// Read one message from a client and act on it. Returns the reply to send back, or
+/// None for liveness probes that connect without sending anything.
+fn handle_connection(
+    stream: &mut impl Read,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+) -> Option<DaemonReply> {
+    let received = match read_message_head(stream) {
+        Ok(received) => received,
+        Err(e) => {
+            return Some(DaemonReply::from_result(
+                None,
+                Err(format!("Failed to read request: {}", e)),
+            ))
+        }
+    };
+    let message = String::from_utf8_lossy(&received).to_string();
+    if message.trim().is_empty() {
+        return None;
+    }
+
+    // Framed messages carry the sender's content
+    if received.starts_with(FRAME_MAGIC.as_bytes()) {
+        let result =
+            read_frame(received, stream, MAX_FRAMED_CONTENT).and_then(|(path, content)| {
+                open_requested(
+                    Path::new(&path),
+                    Some(content),
+                    &OpenOptions::default(),
+                    state,
+                    app_handle,
+                )
+            });
+        return Some(DaemonReply::from_result(None, result));
+    }
+
+    // Newline-delimited JSON requests
+    if message.trim_start().starts_with('{') {
+        return Some(match parse_daemon_request(message.trim()) {
+            Ok((id, request)) => {
+                DaemonReply::from_result(id, handle_daemon_request(request, state, app_handle))
+            }
+            Err(e) => DaemonReply::from_result(None, Err(e)),
+        });
+    }
+
+    // Anything else is a plain path, which may carry a `#anchor` or `:line` target
+    let (path, target) = split_open_target(message.trim_end_matches(['\r', '\n']));
+    let request = DaemonRequest::Open {
+        path,
+        options: OpenOptions::default().with_target(target),
+    };
+    Some(DaemonReply::from_result(
+        None,
+        handle_daemon_request(request, state, app_handle),
+    ))
+}
+
+/// Read through the first newline, or to EOF for clients that send a bare path and
+/// close. Bytes after the newline that arrived in the same read are kept, since a
+/// frame's body follows its header.
+fn read_message_head(stream: &mut impl Read) -> std::io::Result<Vec<u8>> {
+    let mut received = Vec::new();
+    let mut chunk = [0u8; 4096];
+    while !received.contains(&b'\n') {
+        if received.len() > MAX_FRAME_HEADER {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "message header too long",
+            ));
+        }
+        let n = match stream.read(&mut chunk) {
+            Ok(n) => n,
+            // A named pipe client that hung up reads as a broken pipe, not EOF
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => 0,
+            Err(e) => return Err(e),
+        };
+        if n == 0 {
+            break;
+        }
+        received.extend_from_slice(&chunk[..n]);
+    }
+    Ok(received)
+}
+
+/// Parse a JSON request. `op` names the operation; `cmd` is still accepted from older
+/// clients. An `id`, if present, is echoed back in the reply.
+fn parse_daemon_request(
+    message: &str,
+) -> Result<(Option<serde_json::Value>, DaemonRequest), String> {
+    let mut value: serde_json::Value =
+        serde_json::from_str(message).map_err(|e| format!("Invalid request: {}", e))?;
+    let object = value
+        .as_object_mut()
+        .ok_or("Invalid request: expected a JSON object")?;
+    let id = object.remove("id");
+    if let Some(cmd) = object.remove("cmd") {
+        object.entry("op").or_insert(cmd);
+    }
+    let request = serde_json::from_value(value).map_err(|e| format!("Invalid request: {}", e))?;
+    Ok((id, request))
+}
+
+/// Handle a structured daemon request
+fn handle_daemon_request(
+    request: DaemonRequest,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+) -> Result<(), String> {
+    match request {
+        DaemonRequest::Open { path, options } => {
+            open_requested(Path::new(&path), None, &options, state, app_handle)
+        }
+        DaemonRequest::OpenRecent { index } => open_recent(index, state, app_handle),
+    }
+}
+
+/// Open the `index`th entry of the recent-files list and show the window
+fn open_recent(
+    index: usize,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+) -> Result<(), String> {
+    let recent = RecentFiles::load();
+    let entry = recent
+        .entries
+        .get(index)
+        .ok_or_else(|| format!("No recent file at index {}", index))?;
+    load_file_into_state(&PathBuf::from(&entry.path), state, app_handle)?;
+    show_main_window(app_handle);
+    Ok(())
+}
+
+/// Open a file sent by a client in a new tab, or just raise the window if it is
+/// already showing and `reopen_same_file` says to focus
+fn open_requested(
+    path: &Path,
+    content: Option<String>,
+    options: &OpenOptions,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+) -> Result<(), String> {
+    if options.is_default()
+        && is_current_file(path, state)
+        && state.current_config().reopen_same_file() == ReopenSameFile::Focus
+    {
+        state.mark_active();
+        raise_main_window(app_handle);
+        return Ok(());
+    }
+
+    open_in_new_tab(path, content, state, app_handle)?;
+    apply_open_options(options, state);
+    show_main_window(app_handle);
+    Ok(())
+}
+
+/// Apply per-open overrides to the document just loaded. They live on the per-document
+/// state, so the next load starts from the daemon's defaults again.
+fn apply_open_options(options: &OpenOptions, state: &AppState) {
+    if options.no_truncate {
+        *state
+            .is_large_file
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = false;
+    }
+
+    *state.open_target.lock().unwrap_or_else(|e| e.into_inner()) = options
+        .line
+        .map(OpenTarget::Line)
+        .or_else(|| options.anchor.clone().map(OpenTarget::Anchor));
+
+    let section = options.section.as_deref().and_then(|target| {
+        let is_large_file = *state
+            .is_large_file
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+        let index = if is_large_file {
+            find_section(&extract_sections(&content), target)
+        } else {
+            None
+        };
+        if index.is_none() {
+            eprintln!("Socket: Section not found: {}", target);
+        }
+        index
+    });
+    *state
+        .initial_section
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = section;
+}
+
+/// Whether `path` resolves to the file currently loaded
+fn is_current_file(path: &Path, state: &AppState) -> bool {
+    let current = state.file_path.lock().unwrap_or_else(|e| e.into_inner());
+    !current.is_empty()
+        && fs::canonicalize(path).is_ok_and(|canonical| canonical == Path::new(current.as_str()))
+}
+
+/// Show and focus the main window without reloading anything. Focusing also
+/// activates the app on macOS, so this works on every platform without a helper
+/// process.
+fn raise_main_window(app_handle: &tauri::AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        // Make sure window is visible
+        if let Err(e) = window.show() {
+            eprintln!("Failed to show window: {}", e);
+        }
+        if window.is_minimized().unwrap_or(false) {
+            if let Err(e) = window.unminimize() {
+                eprintln!("Failed to unminimize window: {}", e);
+            }
+        }
+        if let Err(e) = window.set_focus() {
+            eprintln!("Failed to focus window: {}", e);
+        }
+    }
+}
+
+/// Show and focus the main window and tell the frontend a new file was loaded
+fn show_main_window(app_handle: &tauri::AppHandle) {
+    raise_main_window(app_handle);
+    if let Some(window) = app_handle.get_webview_window("main") {
+        if let Err(e) = window.emit("file-loaded", ()) {
+            eprintln!("Failed to emit file-loaded event: {}", e);
+        }
+    }
+}
+
+/// Validate and read a file, then make it the current document: updates state,
+/// the window title, the recent-files list, and the watched path.
+/// Returns the new file name.
+fn load_file_into_state(
+    file_path: &Path,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+) -> Result<String, String> {
+    load_file_with_content(file_path, None, state, app_handle)
+}
+
+/// Like `load_file_into_state`, but uses `content` (as read by the sender of a framed
+/// daemon message) instead of re-reading the file when it is given
+fn load_file_with_content(
+    file_path: &Path,
+    content: Option<String>,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+) -> Result<String, String> {
+    // Security: Validate file exists
+    if !file_path.exists() {
+        return Err(format!("File not found: {}", file_path.display()));
+    }
+
+    // Security: Canonicalize path to prevent path traversal
+    let absolute_path =
+        fs::canonicalize(file_path).map_err(|e| format!("Failed to canonicalize path: {}", e))?;
+
+    // Security: Validate it's a supported file (prevent arbitrary file access), unless
+    // it was opened with --force
+    let is_forced = state
+        .forced_files
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(&absolute_path);
+    if !is_forced && classify_file(file_path, &state.current_config()).is_none() {
+        return Err(format!(
+            "Only markdown, PlantUML, and AsciiDoc files are supported: {}",
+            file_path.display()
+        ));
+    }
+
+    // Read file content
+    let new_content = match content {
+        Some(content) => content,
+        None => read_document(&absolute_path)?,
+    };
+
+    if new_content.trim().is_empty() {
+        return Err(format!("File is empty: {}", file_path.display()));
+    }
+
+    let file_size = new_content.len() as u64;
+    let no_truncate = *state.no_truncate.lock().unwrap_or_else(|e| e.into_inner());
+    let is_large_file = exceeds_threshold(file_size, state.large_file_threshold()) && !no_truncate;
+
+    let new_file_name = absolute_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Glance".to_string());
+
+    // Update state (handle poisoned locks gracefully)
+    {
+        let mut content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+        *content = new_content;
+    }
+    {
+        let mut fp = state.file_path.lock().unwrap_or_else(|e| e.into_inner());
+        *fp = absolute_path.to_string_lossy().to_string();
+    }
+    {
+        let mut fn_state = state.file_name.lock().unwrap_or_else(|e| e.into_inner());
+        *fn_state = new_file_name.clone();
+    }
+    {
+        let mut lf = state
+            .is_large_file
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *lf = is_large_file;
+    }
+    *state
+        .last_seen_mtime
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = file_mtime(&absolute_path);
+
+    state.mark_active();
+    if !state.ephemeral {
+        RecentFiles::record(&absolute_path.to_string_lossy());
+        refresh_tray_menu(app_handle);
+    }
+
+    // Update window title
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let window_title = format!("{} - Glance", new_file_name);
+        if let Err(e) = window.set_title(&window_title) {
+            eprintln!("Failed to set window title: {}", e);
+        }
+    }
+
+    // The active tab now shows this file
+    {
+        let mut tabs = state.tabs.lock().unwrap_or_else(|e| e.into_inner());
+        let mut active = state.active_tab.lock().unwrap_or_else(|e| e.into_inner());
+        let tab = Tab {
+            path: absolute_path.to_string_lossy().to_string(),
+            name: new_file_name.clone(),
+        };
+        match tabs.get_mut(*active) {
+            Some(slot) => *slot = tab,
+            None => {
+                tabs.push(tab);
+                *active = tabs.len() - 1;
+            }
+        }
+    }
+
+    // Tell watcher thread to watch new file
+    if let Some(ref sender) = *state
+        .watcher_control
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+    {
+        let _ = sender.send(absolute_path);
+    }
+
+    Ok(new_file_name)
+}
+
+/// Open a file in a new tab, or switch to its tab if it is already open
+fn open_in_new_tab(
+    file_path: &Path,
+    content: Option<String>,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+) -> Result<String, String> {
+    let existing = fs::canonicalize(file_path).ok().and_then(|canonical| {
+        state
+            .tabs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .position(|tab| Path::new(&tab.path) == canonical)
+    });
+
+    // Point the active index at the target tab (a placeholder for a new file), which
+    // the load then fills in
+    let previous_active = {
+        let mut tabs = state.tabs.lock().unwrap_or_else(|e| e.into_inner());
+        let mut active = state.active_tab.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = *active;
+        match existing {
+            Some(index) => *active = index,
+            None if tabs.is_empty() => {}
+            None => {
+                tabs.push(Tab {
+                    path: String::new(),
+                    name: String::new(),
+                });
+                *active = tabs.len() - 1;
+            }
+        }
+        previous
+    };
+
+    let result = load_file_with_content(file_path, content, state, app_handle);
+    if result.is_err() {
+        let mut tabs = state.tabs.lock().unwrap_or_else(|e| e.into_inner());
+        if tabs.last().is_some_and(|tab| tab.path.is_empty()) {
+            tabs.pop();
+        }
+        *state.active_tab.lock().unwrap_or_else(|e| e.into_inner()) = previous_active;
+    }
+    emit_tabs_changed(app_handle);
+    result
+}
+
+/// Close the current document: show the next tab if there is one, otherwise return to
+/// the empty state the app starts in without a file
+#[tauri::command]
+fn close_file(state: tauri::State<AppState>, app: tauri::AppHandle) -> Result<(), String> {
+    let (tab_count, active) = (
+        state.tabs.lock().unwrap_or_else(|e| e.into_inner()).len(),
+        *state.active_tab.lock().unwrap_or_else(|e| e.into_inner()),
+    );
+    if tab_count > 1 {
+        return close_tab(active, state, app);
+    }
+    clear_document(&state, &app);
+    Ok(())
+}
+
+/// Reset to the empty "Glance" state: no content, no tabs, nothing watched
+fn clear_document(state: &AppState, app_handle: &tauri::AppHandle) {
+    reset_document_state(state);
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        if let Err(e) = window.set_title("Glance") {
+            eprintln!("Failed to set window title: {}", e);
+        }
+        if let Err(e) = window.emit("file-closed", ()) {
+            eprintln!("Failed to emit file-closed event: {}", e);
+        }
+    }
+    emit_tabs_changed(app_handle);
+}
+
+/// Drop the current document and tabs and stop watching, without telling the frontend
+fn reset_document_state(state: &AppState) {
+    state
+        .content
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+    state
+        .file_path
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+    *state.file_name.lock().unwrap_or_else(|e| e.into_inner()) = "Glance".to_string();
+    *state
+        .is_large_file
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = false;
+    *state
+        .last_seen_mtime
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = None;
+    state.tabs.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    *state.active_tab.lock().unwrap_or_else(|e| e.into_inner()) = 0;
+
+    // An empty path tells the watcher thread to stop watching
+    if let Some(ref sender) = *state
+        .watcher_control
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+    {
+        let _ = sender.send(PathBuf::new());
+    }
+}
+
+/// Tell the frontend the tab list or active tab changed
+fn emit_tabs_changed(app_handle: &tauri::AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        if let Err(e) = window.emit("tabs-changed", ()) {
+            eprintln!("Failed to emit tabs-changed event: {}", e);
+        }
+    }
+}
+
+/// An open document in the tab list
+#[derive(Clone, serde::Serialize)]
+struct Tab {
+    /// Canonical path of the file
+    path: String,
+    /// File name shown on the tab
+    name: String,
+}
+
+/// Open tabs and which one is shown
+#[derive(Clone, serde::Serialize)]
+struct TabList {
+    tabs: Vec<Tab>,
+    active: usize,
+}
+
+#[tauri::command]
+fn list_tabs(state: tauri::State<AppState>) -> TabList {
+    TabList {
+        tabs: state.tabs.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        active: *state.active_tab.lock().unwrap_or_else(|e| e.into_inner()),
+    }
+}
+
+/// Show the tab at `index`, re-reading its file from disk
+#[tauri::command]
+fn switch_tab(
+    index: usize,
+    state: tauri::State<AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let path = state
+        .tabs
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(index)
+        .map(|tab| tab.path.clone())
+        .ok_or_else(|| format!("No tab at index {}", index))?;
+
+    let previous_active = std::mem::replace(
+        &mut *state.active_tab.lock().unwrap_or_else(|e| e.into_inner()),
+        index,
+    );
+    if let Err(e) = load_file_into_state(Path::new(&path), &state, &app) {
+        *state.active_tab.lock().unwrap_or_else(|e| e.into_inner()) = previous_active;
+        return Err(e);
+    }
+    emit_tabs_changed(&app);
+    Ok(())
+}
+
+/// Close the tab at `index`. Closing the active tab shows its neighbour; closing the
+/// last tab returns to the empty state.
+#[tauri::command]
+fn close_tab(
+    index: usize,
+    state: tauri::State<AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let next = {
+        let mut tabs = state.tabs.lock().unwrap_or_else(|e| e.into_inner());
+        let mut active = state.active_tab.lock().unwrap_or_else(|e| e.into_inner());
+        if index >= tabs.len() {
+            return Err(format!("No tab at index {}", index));
+        }
+
+        tabs.remove(index);
+        if tabs.is_empty() {
+            drop(tabs);
+            drop(active);
+            clear_document(&state, &app);
+            return Ok(());
+        }
+        if index < *active {
+            *active -= 1;
+            None
+        } else if index == *active {
+            *active = index.min(tabs.len() - 1);
+            Some(tabs[*active].path.clone())
+        } else {
+            None
+        }
+    };
+
+    if let Some(path) = next {
+        load_file_into_state(Path::new(&path), &state, &app)?;
+    }
+    emit_tabs_changed(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_markdown_content(state: tauri::State<AppState>) -> MarkdownContent {
+    build_markdown_content(&state)
+}
+
+/// Assemble the frontend payload for the current document
+fn build_markdown_content(state: &AppState) -> MarkdownContent {
+    // Use unwrap_or_else to handle poisoned locks gracefully
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    let file_path = state.file_path.lock().unwrap_or_else(|e| e.into_inner());
+    let file_name = state.file_name.lock().unwrap_or_else(|e| e.into_inner());
+    let is_large_file = *state
+        .is_large_file
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    // Get directory of the markdown file for resolving relative image paths
+    let file_dir = PathBuf::from(file_path.as_str())
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let config = state.current_config();
+
+    // Present YAML front matter per config (line numbers are preserved)
+    let mut front_matter = Vec::new();
+    let content = match front_matter::parse_front_matter(&content) {
+        Some(fm) => match config.front_matter_display() {
+            FrontMatterDisplay::Hide => front_matter::strip(&content, &fm),
+            FrontMatterDisplay::Table => {
+                front_matter = fm
+                    .entries()
+                    .into_iter()
+                    .map(|(key, value)| FrontMatterEntry { key, value })
+                    .collect();
+                front_matter::strip(&content, &fm)
+            }
+            FrontMatterDisplay::Raw => front_matter::as_code_block(&content, &fm),
+        },
+        None => content.clone(),
+    };
+
+    // Apply content transforms enabled by extensions
+    let content = if config.extensions.emoji {
+        emoji::replace_shortcodes(&content)
+    } else {
+        content
+    };
+
+    // Check if this is a PlantUML or CSV/TSV file
+    let path = PathBuf::from(file_path.as_str());
+    let render_mode = classify_file(&path, &config).unwrap_or(RenderMode::Markdown);
+    let is_plantuml_file = render_mode == RenderMode::Plantuml;
+    let is_table_file = render_mode == RenderMode::Table;
+    // AsciiDoc headings (`== Title`) aren't markdown headings, so it gets no outline
+    let has_markdown_headings = !is_table_file && render_mode != RenderMode::Asciidoc;
+
+    // Headings for the TOC, scanned regardless of file size
+    let toc = if has_markdown_headings {
+        extract_outline(&content)
+    } else {
+        Vec::new()
+    };
+
+    // Extract sections (and their nesting) if in large file mode
+    let (mut sections, section_tree) = if is_large_file && has_markdown_headings {
+        (extract_sections(&content), build_section_tree(&toc))
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    // Hierarchical heading numbers: per section in large-file mode, and in outline
+    // order for the normal renderer and TOC
+    let heading_numbers = if config.number_headings {
+        number_sections(&mut sections);
+        let levels: Vec<u8> = toc.iter().map(|e| e.level).collect();
+        markdown::number_headings(&levels)
+    } else {
+        Vec::new()
+    };
+
+    // Show the TOC when configured, otherwise when there are enough headings
+    let show_toc = config
+        .show_toc
+        .unwrap_or_else(|| toc.len() > TOC_AUTO_MIN_HEADINGS);
+
+    // Locate <details> blocks for expand/collapse-all and keyboard navigation
+    let details_blocks = markdown::find_details_blocks(&content);
+
+    // Flag tables likely to overflow the content width
+    let wide_tables = markdown::find_wide_tables(&content, WIDE_TABLE_COLUMNS, WIDE_TABLE_CHARS);
+
+    // Authored start numbers the webview would otherwise reset to 1
+    let ordered_list_starts = markdown::find_ordered_list_starts(&content);
+
+    // Task list checkboxes the frontend can make clickable
+    let task_items = if render_mode == RenderMode::Markdown {
+        markdown::find_task_items(&content)
+    } else {
+        Vec::new()
+    };
+
+    // Fenced diagrams for the frontend to render in place of their code blocks, in
+    // one scan for both extensions
+    let is_markdown = render_mode == RenderMode::Markdown;
+    let mut diagram_languages = Vec::new();
+    if config.extensions.plantuml && is_markdown {
+        diagram_languages.extend(["plantuml", "puml"]);
+    }
+    if config.extensions.mermaid && is_markdown {
+        diagram_languages.push("mermaid");
+    }
+    let diagram_fences = if diagram_languages.is_empty() {
+        Vec::new()
+    } else {
+        markdown::find_code_fences(&content, &diagram_languages)
+    };
+    let (mermaid_fences, plantuml_fences): (Vec<_>, Vec<_>) = diagram_fences
+        .into_iter()
+        .partition(|fence| fence.language == "mermaid");
+
+    // Resolve wikilinks against the markdown files next to the document
+    let wikilinks = if config.extensions.wikilinks {
+        resolve_wikilinks(&content, Path::new(&file_dir), &config)
+    } else {
+        Vec::new()
+    };
+
+    // Parse delimited data for table rendering
+    let (table, table_truncated) = if is_table_file {
+        let is_tsv = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("tsv"));
+        let delimiter = if is_tsv {
+            '\t'
+        } else {
+            delimited::detect_delimiter(&content)
+        };
+        let parsed =
+            delimited::parse_delimited(&content, delimiter, MAX_TABLE_ROWS, MAX_TABLE_COLUMNS);
+        (parsed.rows, parsed.truncated)
+    } else {
+        (Vec::new(), false)
+    };
+
+    // Validate a pending `#anchor` / `:line` target against this document
+    let scroll_to = state
+        .open_target
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .and_then(|target| resolve_scroll_target(target, &content, &toc));
+
+    MarkdownContent {
+        content,
+        file_path: file_path.clone(),
+        file_name: file_name.clone(),
+        file_dir,
+        is_large_file,
+        sections,
+        extensions: config.extensions,
+        is_plantuml_file,
+        reduce_motion: config.reduce_motion,
+        render_mode,
+        table,
+        table_truncated,
+        show_toc,
+        initial_section: state
+            .initial_section
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take(),
+        scroll_to,
+        content_max_width: config.content_max_width,
+        custom_css: state
+            .custom_css
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone(),
+        details_blocks,
+        wide_tables,
+        ordered_list_starts,
+        task_items,
+        plantuml_fences,
+        mermaid_fences,
+        wikilinks,
+        number_headings: config.number_headings,
+        heading_numbers,
+        front_matter,
+        section_tree,
+        toc,
+    }
+}
+
+/// A wikilink and the file it points to
+#[derive(Clone, serde::Serialize)]
+struct WikiLinkRef {
+    target: String,
+    alias: Option<String>,
+    /// Line number of the link (0-indexed)
+    line: usize,
+    /// Byte offsets of the link within the line
+    start: usize,
+    end: usize,
+    /// Absolute path of the matching markdown file; None if unresolved.
+    /// The frontend navigates by passing this to follow_link.
+    resolved_path: Option<String>,
+}
+
+/// Find wikilinks and match each target to a markdown file in `dir`: a relative
+/// path first (`[[notes/Page]]`), then a case-insensitive file-name match
+fn resolve_wikilinks(content: &str, dir: &Path, config: &AppConfig) -> Vec<WikiLinkRef> {
+    let links = markdown::extract_wikilinks(content);
+    if links.is_empty() {
+        return Vec::new();
+    }
+
+    let candidates: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| classify_file(path, config) == Some(RenderMode::Markdown))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    links
+        .into_iter()
+        .map(|link| {
+            let direct = file_types::MARKDOWN_EXTENSIONS
+                .iter()
+                .map(|ext| dir.join(format!("{}.{}", link.target, ext)))
+                .find(|path| path.is_file());
+            let resolved = direct.or_else(|| {
+                let wanted = link.target.to_lowercase();
+                candidates
+                    .iter()
+                    .find(|path| {
+                        path.file_stem()
+                            .is_some_and(|stem| stem.to_string_lossy().to_lowercase() == wanted)
+                    })
+                    .cloned()
+            });
+
+            WikiLinkRef {
+                target: link.target,
+                alias: link.alias,
+                line: link.line,
+                start: link.start,
+                end: link.end,
+                resolved_path: resolved.map(|path| path.to_string_lossy().to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Intra-document link whose `#anchor` matches no heading
+#[derive(Clone, serde::Serialize)]
+struct BrokenAnchor {
+    /// Anchor without the leading `#`
+    anchor: String,
+    /// Link text
+    text: String,
+    /// Line number of the link (0-indexed)
+    line: usize,
+}
+
+/// Find `[text](#anchor)` links that don't resolve to a heading slug
+#[tauri::command]
+fn check_anchors(state: tauri::State<AppState>) -> Vec<BrokenAnchor> {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    let slugs: HashSet<String> = heading_slugs(&extract_outline(&content))
+        .into_iter()
+        .collect();
+
+    markdown::extract_links(&content)
+        .into_iter()
+        .filter(|link| !link.is_image)
+        .filter_map(|link| {
+            let anchor = link.target.strip_prefix('#')?.to_string();
+            if slugs.contains(&anchor) {
+                None
+            } else {
+                Some(BrokenAnchor {
+                    anchor,
+                    text: link.text,
+                    line: link.line,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes in a link target
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = input
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// Follow a relative link to another local markdown file under the current document's
+/// folder, loading it in place
+#[tauri::command]
+fn follow_link(
+    href: String,
+    state: tauri::State<AppState>,
+    app: tauri::AppHandle,
+) -> Result<MarkdownContent, String> {
+    if is_remote_src(&href) {
+        return Err(format!("Not a local link: {}", href));
+    }
+
+    let current_path = state
+        .file_path
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let file_dir = PathBuf::from(&current_path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    // The same jail as images: links only reach files under the document's folder,
+    // including through symlinks
+    let target = resolve_within(&file_dir, &percent_decode(&href))
+        .ok_or_else(|| format!("Link is missing or outside the document's folder: {}", href))?;
+
+    if classify_file(&target, &state.current_config()) != Some(RenderMode::Markdown) {
+        return Err(format!("Not a markdown link: {}", href));
+    }
+
+    load_file_into_state(&target, &state, &app)?;
+
+    // Remember where we came from
+    if !current_path.is_empty() {
+        let mut history = state.history.lock().unwrap_or_else(|e| e.into_inner());
+        history.push(current_path);
+        if history.len() > MAX_HISTORY {
+            history.remove(0);
+        }
+    }
+
+    Ok(build_markdown_content(&state))
+}
+
+/// Go back to the previous file in the link navigation history
+#[tauri::command]
+fn navigate_back(
+    state: tauri::State<AppState>,
+    app: tauri::AppHandle,
+) -> Result<MarkdownContent, String> {
+    let previous = state
+        .history
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .pop()
+        .ok_or_else(|| "No previous file".to_string())?;
+    load_file_into_state(&PathBuf::from(previous), &state, &app)?;
+    Ok(build_markdown_content(&state))
+}
+
+/// Section ids in document order, for keyboard navigation of the nested accordion
+#[tauri::command]
+fn get_section_ids(state: tauri::State<AppState>) -> Vec<String> {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    all_section_ids(&build_section_tree(&extract_outline(&content)))
+}
+
+/// Document outline (level, title, line per heading) without the content payload,
+/// for a quick-jump palette
+#[tauri::command]
+fn get_outline(state: tauri::State<AppState>) -> Vec<OutlineEntry> {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    extract_outline(&content)
+}
+
+/// Chain of headings enclosing `line`, from the outermost down to the nearest heading
+/// at or above it. Empty before the first heading; headings inside code fences are ignored.
+#[tauri::command]
+fn breadcrumb_for_line(line: usize, state: tauri::State<AppState>) -> Vec<OutlineEntry> {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    let mut chain: Vec<OutlineEntry> = Vec::new();
+
+    for entry in extract_outline(&content) {
+        if entry.line > line {
+            break;
+        }
+        // A heading closes any open headings at the same or deeper level
+        while chain.last().is_some_and(|last| last.level >= entry.level) {
+            chain.pop();
+        }
+        chain.push(entry);
+    }
+
+    chain
+}
+
+/// CSS units accepted for `content_max_width`
+const CSS_LENGTH_UNITS: &[&str] = &[
+    "px", "rem", "em", "ch", "ex", "vw", "pt", "cm", "mm", "in", "%",
+];
+
+/// Loose CSS length check: a positive number followed by a known unit
+fn is_valid_css_length(value: &str) -> bool {
+    CSS_LENGTH_UNITS.iter().any(|unit| {
+        value
+            .strip_suffix(unit)
+            .and_then(|number| number.parse::<f64>().ok())
+            .is_some_and(|number| number > 0.0 && number.is_finite())
+    })
+}
+
+/// Persist the max content width (None = full width)
+#[tauri::command]
+fn set_content_width(value: Option<String>, state: tauri::State<AppState>) -> Result<(), String> {
+    let value = value.map(|v| v.trim().to_lowercase());
+    if let Some(width) = &value {
+        if !is_valid_css_length(width) {
+            return Err(format!("Invalid CSS length: {}", width));
+        }
+    }
+
+    let mut config = state.current_config();
+    config.content_max_width = value;
+    persist_config(&config, &state)
+}
+
+/// Built-in keyboard shortcuts: (action, accelerator, description)
+const DEFAULT_SHORTCUTS: &[(&str, &str, &str)] = &[
+    ("open_file", "CmdOrCtrl+O", "Open a file"),
+    ("zoom_in", "CmdOrCtrl+=", "Zoom in"),
+    ("zoom_out", "CmdOrCtrl+-", "Zoom out"),
+    ("zoom_reset", "CmdOrCtrl+0", "Reset zoom"),
+    ("reload", "CmdOrCtrl+R", "Reload the file from disk"),
+];
+
+/// A keyboard shortcut as currently bound
+#[derive(Clone, serde::Serialize)]
+struct ShortcutInfo {
+    action: String,
+    accelerator: String,
+    description: String,
+    /// False when remapped in `shortcuts` config
+    is_default: bool,
+}
+
+/// Search the current document's raw text, returning hits in document order for the
+/// frontend to step through. `query` is literal unless `regex` is set.
+#[tauri::command]
+fn search_content(
+    query: String,
+    case_sensitive: bool,
+    regex: Option<bool>,
+    state: tauri::State<AppState>,
+) -> Result<Vec<search::SearchMatch>, String> {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    search::find_matches(&content, &query, case_sensitive, regex.unwrap_or(false))
+}
+
+/// Supported files under the directory glance was opened on, for the sidebar. Empty
+/// outside directory mode. Open an entry with open_dropped_file.
+#[tauri::command]
+fn list_directory_files(state: tauri::State<AppState>) -> Vec<directory::FileEntry> {
+    let Some(dir) = &state.directory else {
+        return Vec::new();
+    };
+    let config = state.current_config();
+    directory::scan(dir, &|path| classify_file(path, &config).is_some())
+}
+
+/// Recently opened files, most recent first, for a "Recent" menu. Reopen an entry
+/// with open_dropped_file.
+#[tauri::command]
+fn get_recent_files() -> Vec<RecentFileEntry> {
+    RecentFiles::load().entries
+}
+
+/// Remember how far `path` is scrolled so reopening it can return there
+#[tauri::command]
+fn save_scroll_position(
+    path: String,
+    offset: f64,
+    state: tauri::State<AppState>,
+) -> Result<(), String> {
+    if state.ephemeral || path.is_empty() || !offset.is_finite() {
+        return Ok(());
+    }
+    let mut positions = ScrollPositions::load();
+    positions.set(&per_file_key(&path), offset.max(0.0));
+    positions
+        .save()
+        .map_err(|e| format!("Failed to save scroll position: {}", e))
+}
+
+/// Last saved scroll offset for `path`, if any
+#[tauri::command]
+fn get_scroll_position(path: String) -> Option<f64> {
+    ScrollPositions::load()
+        .positions
+        .get(&per_file_key(&path))
+        .map(|position| position.offset)
+}
+
+/// Shortcuts for the help overlay: defaults with the user's config overrides applied
+#[tauri::command]
+fn list_shortcuts(state: tauri::State<AppState>) -> Vec<ShortcutInfo> {
+    let config = state.current_config();
+    for action in config.shortcuts.keys() {
+        if !DEFAULT_SHORTCUTS.iter().any(|(name, _, _)| name == action) {
+            eprintln!("Unknown shortcut action '{}', ignoring", action);
+        }
+    }
+
+    DEFAULT_SHORTCUTS
+        .iter()
+        .map(|(action, default, description)| {
+            let remapped = config.shortcuts.get(*action);
+            ShortcutInfo {
+                action: action.to_string(),
+                accelerator: remapped.map_or(*default, String::as_str).to_string(),
+                description: description.to_string(),
+                is_default: remapped.is_none(),
+            }
+        })
+        .collect()
+}
+
+/// Persist whether the TOC sidebar is shown
+#[tauri::command]
+fn set_show_toc(value: bool, state: tauri::State<AppState>) -> Result<(), String> {
+    let mut config = state.current_config();
+    config.show_toc = Some(value);
+    persist_config(&config, &state)
+}
+
+/// Get the current document's source exactly as read from disk (no content transforms)
+#[tauri::command]
+fn get_raw_content(state: tauri::State<AppState>) -> String {
+    state
+        .content
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Counts for a status-bar readout
+#[derive(Clone, serde::Serialize)]
+struct DocumentStats {
+    words: usize,
+    characters: usize,
+    /// Lines in the whole file, front matter included, to match an editor's numbering
+    lines: usize,
+    headings: usize,
+    /// Fenced code blocks
+    code_blocks: usize,
+    /// Estimated at `reading_wpm` (default 200), rounded up to whole minutes
+    reading_minutes: usize,
+}
+
+/// Word, character, heading, and code-block counts for the current document, plus an
+/// estimated reading time. Front matter isn't counted, and words inside fenced code
+/// blocks only count with `include_code`.
+#[tauri::command]
+fn document_stats(include_code: Option<bool>, state: tauri::State<AppState>) -> DocumentStats {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    let wpm = state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .reading_wpm
+        .filter(|wpm| *wpm > 0)
+        .unwrap_or(DEFAULT_READING_WPM) as usize;
+    let include_code = include_code.unwrap_or(false);
+    let (_, body) = split_front_matter(&content);
+
+    let mut words = 0;
+    let mut code_blocks = 0;
+    let mut in_code_block = false;
+    for line in body.lines() {
+        if markdown::is_fence_line(line) {
+            if !in_code_block {
+                code_blocks += 1;
+            }
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block && !include_code {
+            continue;
+        }
+        words += line.split_whitespace().count();
+    }
+
+    DocumentStats {
+        words,
+        characters: body.chars().count(),
+        lines: content.lines().count(),
+        headings: extract_outline(body).len(),
+        code_blocks,
+        reading_minutes: words.div_ceil(wpm),
+    }
+}
+
+/// Export the current document as plain text with markdown syntax stripped.
+/// Front matter is left out unless `include_front_matter` is set.
+#[tauri::command]
+fn export_plaintext(include_front_matter: Option<bool>, state: tauri::State<AppState>) -> String {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    let (front_matter, body) = split_front_matter(&content);
+    let text = plaintext::to_plain_text(body);
+    match front_matter.filter(|_| include_front_matter.unwrap_or(false)) {
+        Some(fm) => format!("{}\n\n{}", fm.raw, text),
+        None => text,
+    }
+}
+
+/// Split a document into its front matter (if any) and the body after it
+fn split_front_matter(content: &str) -> (Option<front_matter::FrontMatter>, &str) {
+    match front_matter::parse_front_matter(content) {
+        Some(fm) => {
+            let body = front_matter::body(content, &fm);
+            (Some(fm), body)
+        }
+        None => (None, content),
+    }
+}
+
+/// Result of a content_changed_since poll
+#[derive(Clone, serde::Serialize)]
+struct ContentStatus {
+    changed: bool,
+    /// Hash of the current content (same hash the watcher uses)
+    hash: u64,
+}
+
+/// Polling alternative to the `file-changed` event: compare the current content
+/// against a hash from an earlier poll
+#[tauri::command]
+fn content_changed_since(hash: u64, state: tauri::State<AppState>) -> ContentStatus {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    let current = content_hash(&content);
+    ContentStatus {
+        changed: current != hash,
+        hash: current,
+    }
+}
+
+/// Toggle full rendering of large files at runtime. Recomputes large-file mode for the
+/// current document and asks the frontend to re-fetch; returns the new `is_large_file`.
+#[tauri::command]
+fn set_no_truncate(
+    value: bool,
+    state: tauri::State<AppState>,
+    window: tauri::WebviewWindow,
+) -> Result<bool, String> {
+    *state.no_truncate.lock().unwrap_or_else(|e| e.into_inner()) = value;
+
+    let size = state
+        .content
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .len() as u64;
+    let is_large_file = exceeds_threshold(size, state.large_file_threshold()) && !value;
+    *state
+        .is_large_file
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = is_large_file;
+
+    window
+        .emit("file-changed", ())
+        .map_err(|e| format!("Failed to emit file-changed event: {}", e))?;
+    Ok(is_large_file)
+}
+
+/// Force a re-read of the current file from disk, for changes the watcher missed
+/// (network filesystems, atomic saves via rename). Always emits `file-changed`.
+#[tauri::command]
+fn reload_file(state: tauri::State<AppState>, window: tauri::WebviewWindow) -> Result<(), String> {
+    let path = PathBuf::from(
+        state
+            .file_path
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone(),
+    );
+    if path.as_os_str().is_empty() {
+        return Err("No file is open".to_string());
+    }
+    if !path.exists() {
+        return Err(format!("File no longer exists: {}", path.display()));
+    }
+
+    let new_content = read_document(&path)?;
+    let no_truncate = *state.no_truncate.lock().unwrap_or_else(|e| e.into_inner());
+    let is_large_file =
+        exceeds_threshold(new_content.len() as u64, state.large_file_threshold()) && !no_truncate;
+
+    *state.content.lock().unwrap_or_else(|e| e.into_inner()) = new_content;
+    *state
+        .is_large_file
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = is_large_file;
+    *state
+        .last_seen_mtime
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = file_mtime(&path);
+    state.mark_active();
+
+    window
+        .emit("file-changed", ())
+        .map_err(|e| format!("Failed to emit file-changed event: {}", e))
+}
+
+/// Check or uncheck the task list item on `line` (0-indexed) and save the file. Only
+/// the character between the brackets changes, so indentation, list markers and line
+/// endings are left as they were.
+#[tauri::command]
+fn toggle_task(line: usize, checked: bool, state: tauri::State<AppState>) -> Result<(), String> {
+    let path = PathBuf::from(
+        state
+            .file_path
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone(),
+    );
+    if path.as_os_str().is_empty() {
+        return Err("No file is open".to_string());
+    }
+
+    // Edit what's on disk, not the cached copy, so an external edit the watcher
+    // hasn't reloaded yet isn't overwritten
+    let on_disk = file_types::read_text(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let shown = state
+        .content
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let Some(updated) = toggled_task_content(&on_disk, &shown, line, checked)? else {
+        return Ok(());
+    };
+
+    let read_only = fs::metadata(&path)
+        .map(|m| m.permissions().readonly())
+        .unwrap_or(false);
+    if read_only {
+        return Err(format!("{} is read-only", path.display()));
+    }
+
+    // With an external edit elsewhere in the file not shown yet, save without claiming
+    // the write, so the watcher reloads the view with both changes
+    let saved = if on_disk == shown {
+        state.write_document(&path, updated)
+    } else {
+        fs::write(&path, updated)
+    };
+    saved.map_err(|e| match e.kind() {
+        std::io::ErrorKind::PermissionDenied => format!("{} is read-only", path.display()),
+        _ => format!("Failed to save {}: {}", path.display(), e),
+    })?;
+    state.mark_active();
+    Ok(())
+}
+
+/// The file's content after checking or unchecking the task on `line`, given what's on
+/// disk and what the view was rendered from. None when the box is already in that
+/// state. Fails if the line on disk no longer matches the one shown.
+fn toggled_task_content(
+    on_disk: &str,
+    shown: &str,
+    line: usize,
+    checked: bool,
+) -> Result<Option<String>, String> {
+    if on_disk.lines().nth(line) != shown.lines().nth(line) {
+        return Err(format!(
+            "Line {} changed on disk; reload and try again",
+            line + 1
+        ));
+    }
+    let updated = markdown::set_task_checked(on_disk, line, checked)
+        .ok_or_else(|| format!("No task list item on line {}", line + 1))?;
+    Ok((updated != on_disk).then_some(updated))
+}
+
+/// Markdown bundled for a share action
+#[derive(Clone, serde::Serialize)]
+struct Snippet {
+    title: String,
+    markdown: String,
+    /// Languages of the fenced code blocks it contains
+    language_hints: Vec<String>,
+}
+
+/// Export the whole document, or one section, as a shareable snippet. Local only;
+/// posting it anywhere is up to the frontend. With `include_front_matter`, the
+/// document's front matter is prepended so the snippet stands alone.
+#[tauri::command]
+fn export_snippet(
+    section_index: Option<usize>,
+    include_front_matter: Option<bool>,
+    state: tauri::State<AppState>,
+) -> Result<Snippet, String> {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    let (front_matter, content) = split_front_matter(&content);
+
+    let (title, markdown) = match section_index {
+        Some(index) => {
+            let sections = extract_sections(&content);
+            let section = sections.get(index).ok_or_else(|| {
+                format!(
+                    "Section index {} out of range ({} sections)",
+                    index,
+                    sections.len()
+                )
+            })?;
+            (
+                plaintext::strip_inline(&section.title),
+                section.content.clone(),
+            )
+        }
+        None => {
+            let outline = extract_outline(&content);
+            let title = outline
+                .iter()
+                .find(|entry| entry.level == 1)
+                .or(outline.first())
+                .map(|entry| plaintext::strip_inline(&entry.title))
+                .unwrap_or_else(|| {
+                    state
+                        .file_name
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .clone()
+                });
+            (title, content.to_string())
+        }
+    };
+
+    // Prepend the original front matter so the snippet stands alone
+    let markdown = match front_matter.filter(|_| include_front_matter.unwrap_or(false)) {
+        Some(fm) => format!("{}\n{}", fm.to_yaml_block(), markdown),
+        None => markdown,
+    };
+
+    Ok(Snippet {
+        title,
+        language_hints: markdown::fence_languages(&markdown),
+        markdown,
+    })
+}
+
+/// Flat map of the document's top-level blocks (headings, paragraphs, code, lists,
+/// blockquotes, tables, thematic breaks) with their line ranges, for tooling
+#[tauri::command]
+fn get_block_structure(state: tauri::State<AppState>) -> Vec<markdown::Block> {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    markdown::block_structure(&content)
+}
+
+/// Split the document into speakable blocks for read-aloud, capped at `MAX_PARAGRAPHS`
+#[tauri::command]
+fn get_paragraphs(state: tauri::State<AppState>) -> Vec<plaintext::Paragraph> {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    let mut paragraphs = plaintext::to_paragraphs(&content);
+    paragraphs.truncate(MAX_PARAGRAPHS);
+    paragraphs
+}
+
+/// File metadata for the status bar
+#[derive(Clone, serde::Serialize)]
+struct FileMeta {
+    size_bytes: u64,
+    /// Last modification time (Unix seconds)
+    modified_unix: Option<u64>,
+    /// Creation time (Unix seconds), where the filesystem records it
+    created_unix: Option<u64>,
+}
+
+fn unix_seconds(time: std::io::Result<SystemTime>) -> Option<u64> {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+}
+
+/// Get size and timestamps of the current file (None when there's no backing file)
+#[tauri::command]
+fn file_meta(state: tauri::State<AppState>) -> Option<FileMeta> {
+    let file_path = state
+        .file_path
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if file_path.is_empty() {
+        return None;
+    }
+
+    let metadata = fs::metadata(&file_path).ok()?;
+    Some(FileMeta {
+        size_bytes: metadata.len(),
+        modified_unix: unix_seconds(metadata.modified()),
+        created_unix: unix_seconds(metadata.created()),
+    })
+}
+
+/// Resolve HTML render options from the app config
+fn render_options(config: &AppConfig) -> render::RenderOptions {
+    render::RenderOptions {
+        emoji: config.extensions.emoji,
+        ..Default::default()
+    }
+}
+
+/// `content` as a self-contained HTML page: the viewer's stylesheet inlined, and local
+/// images embedded as data URIs, resolved against the directory of `file_path` as the
+/// viewer resolves them. Front matter is left out. Never truncated, whatever the size.
+fn standalone_html(content: &str, file_path: &str, file_name: &str, config: &AppConfig) -> String {
+    let file_dir = Path::new(file_path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let max_image_size = config.max_image_size();
+    let (_, body) = split_front_matter(content);
+    let html = render::render_markdown_with_images(body, &render_options(config), &|src| {
+        // Piped and remote documents have no directory to resolve against
+        if file_dir.as_os_str().is_empty() {
+            return None;
+        }
+        // Same jail and size limit as load_image: nothing outside the document's
+        // directory tree ends up in the exported page
+        let path = resolve_within(&file_dir, &percent_decode(src))?;
+        render::image_data_uri(&path, max_image_size)
+    });
+    render::standalone_document(file_name, &html)
+}
+
+/// The current document as a standalone HTML page (see standalone_html), even for a
+/// large file the viewer shows in sections. Returns the file name with the page.
+fn standalone_export(state: &AppState) -> Result<(String, String), String> {
+    let content = state
+        .content
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let file_path = state
+        .file_path
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let file_name = state
+        .file_name
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if content.trim().is_empty() {
+        return Err("No document to export".to_string());
+    }
+
+    let config = state.current_config();
+    let document = standalone_html(&content, &file_path, &file_name, &config);
+    Ok((file_name, document))
+}
+
+/// `out_path`, or where the user chooses to save the export of `file_name` when it is
+/// None. The dialog blocks until answered, so only call this from async commands.
+fn export_path(
+    app: &tauri::AppHandle,
+    out_path: Option<String>,
+    file_name: &str,
+    filter: (&str, &[&str]),
+) -> Result<PathBuf, String> {
+    if let Some(path) = out_path {
+        return Ok(PathBuf::from(path));
+    }
+    let (filter_name, extensions) = filter;
+    let stem = Path::new(file_name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "document".to_string());
+    app.dialog()
+        .file()
+        .add_filter(filter_name, extensions)
+        .set_file_name(format!("{}.{}", stem, extensions[0]))
+        .blocking_save_file()
+        .ok_or_else(|| "Export cancelled".to_string())?
+        .into_path()
+        .map_err(|e| format!("Invalid save path: {}", e))
+}
+
+/// Export the current document as a self-contained HTML file. Asks where to save when
+/// `out_path` is None. Returns the path written.
+#[tauri::command]
+async fn export_html(
+    out_path: Option<String>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let (file_name, document) = standalone_export(&state)?;
+    let out_path = export_path(&app, out_path, &file_name, ("HTML", &["html", "htm"]))?;
+    fs::write(&out_path, document)
+        .map_err(|e| format!("Failed to write {}: {}", out_path.display(), e))?;
+    Ok(out_path.to_string_lossy().to_string())
+}
+
+/// Export the current document to PDF, printed from the same page export_html writes.
+/// `options` sets the page size, margins and orientation (A4 portrait, 15mm margins by
+/// default). Asks where to save when `out_path` is None. Returns the path written.
+#[tauri::command]
+async fn export_pdf(
+    out_path: Option<String>,
+    options: Option<pdf::PdfOptions>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    // Reject bad options before asking where to save
+    options.paper_name()?;
+    options.margin()?;
+    let (file_name, document) = standalone_export(&state)?;
+    let out_path = export_path(&app, out_path, &file_name, ("PDF", &["pdf"]))?;
+    pdf::print_to_pdf(&app, document, &out_path, &options)?;
+    Ok(out_path.to_string_lossy().to_string())
+}
+
+/// Render PlantUML source to SVG with a local PlantUML (`plantuml_jar`, or `plantuml` /
+/// plantuml.jar on PATH) or else the configured PlantUML server. Results are cached, so
+/// unchanged diagrams aren't re-rendered on every reload.
+#[tauri::command]
+async fn render_plantuml(
+    source: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let backend = plantuml_backend(&state);
+    let mut hasher = DefaultHasher::new();
+    (&backend, &source).hash(&mut hasher);
+    let key = hasher.finish();
+
+    if let Some(svg) = state
+        .plantuml_cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&key)
+    {
+        return Ok(svg.clone());
+    }
+
+    // PlantUML runs a process or makes a request, so keep it off the async runtime
+    let svg = tauri::async_runtime::spawn_blocking(move || backend.render(&source))
+        .await
+        .map_err(|e| format!("PlantUML render failed: {}", e))??;
+    let mut cache = state
+        .plantuml_cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if cache.len() >= MAX_CACHED_DIAGRAMS {
+        cache.clear();
+    }
+    cache.insert(key, svg.clone());
+    Ok(svg)
+}
+
+/// The backend render_plantuml uses, chosen from the config the first time it's needed
+fn plantuml_backend(state: &AppState) -> plantuml::Backend {
+    state
+        .plantuml_backend
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(|| {
+            let config = state.config.lock().unwrap_or_else(|e| e.into_inner());
+            plantuml::Backend::select(
+                config.extensions.plantuml_jar.as_deref(),
+                config.extensions.plantuml_server.as_deref(),
+            )
+        })
+        .clone()
+}
+
+/// Highlight a code block as `lang` with the configured `code_theme`, as HTML with inline
+/// styles. Unknown languages and themes come back as a plain `<pre>`.
+#[tauri::command]
+async fn highlight_code(
+    lang: String,
+    code: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let code_theme = state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .code_theme
+        .clone();
+    Ok(highlight::highlight(&lang, &code, code_theme.as_deref()))
+}
+
+/// Names of the themes `code_theme` accepts
+#[tauri::command]
+fn list_code_themes() -> Vec<String> {
+    highlight::theme_names()
+}
+
+/// Render the current document to HTML without the webview. With
+/// `include_front_matter`, front matter is prepended as a metadata list.
+#[tauri::command]
+fn render_html(include_front_matter: Option<bool>, state: tauri::State<AppState>) -> String {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    let (front_matter, body) = split_front_matter(&content);
+    let html = render::render_markdown_to_html(body, &render_options(&state.current_config()));
+    match front_matter.filter(|_| include_front_matter.unwrap_or(false)) {
+        Some(fm) => format!("{}{}", render::metadata_block_html(&fm.entries()), html),
+        None => html,
+    }
+}
+
+/// Answer a `confirm-close` prompt: quit the app or just hide the window
+#[tauri::command]
+fn confirm_close(
+    quit: bool,
+    state: tauri::State<AppState>,
+    window: tauri::WebviewWindow,
+    app: tauri::AppHandle,
+) {
+    if quit {
+        remove_socket_file(&state);
+        app.exit(0);
+        return;
+    }
+
+    state.mark_active();
+    if let Err(e) = window.hide() {
+        eprintln!("Failed to hide window: {}", e);
+    }
+}
+
+/// Persist the color theme and apply it to the window, emitting `theme-changed`
+#[tauri::command]
+fn set_theme(
+    theme: Theme,
+    state: tauri::State<AppState>,
+    window: tauri::WebviewWindow,
+) -> Result<(), String> {
+    let mut config = state.current_config();
+    config.theme = theme;
+    persist_config(&config, &state)?;
+    apply_theme(&window, theme);
+    Ok(())
+}
+
+/// Force `theme` on the window (or follow the OS) and emit `theme-changed`
+fn apply_theme(window: &tauri::WebviewWindow, theme: Theme) {
+    if let Err(e) = window.set_theme(theme.window_theme()) {
+        eprintln!("Failed to set window theme: {}", e);
+    }
+    let os_theme = window.theme().unwrap_or(tauri::Theme::Light);
+    if let Err(e) = window.emit("theme-changed", ThemeChanged::new(theme, os_theme)) {
+        eprintln!("Failed to emit theme-changed event: {}", e);
+    }
+}
+
+/// Persist the reduced-motion preference (None = follow the system preference)
+#[tauri::command]
+fn set_reduce_motion(value: Option<bool>, state: tauri::State<AppState>) -> Result<(), String> {
+    let mut config = state.current_config();
+    config.reduce_motion = value;
+    persist_config(&config, &state)
+}
+
+/// Settings the webview may change through update_config. Anything naming a path,
+/// a program to run or a network address (custom_css, extensions.plantuml_jar,
+/// remote_listen, ...) is left to config.toml.
+const UI_SETTINGS: &[&str] = &[
+    "no_truncate",
+    "reduce_motion",
+    "reopen_last_file",
+    "content_max_width",
+    "show_toc",
+    "close_behavior",
+    "front_matter_display",
+    "reopen_same_file",
+    "on_delete",
+    "number_headings",
+    "shortcuts",
+    "large_file_threshold",
+    "code_theme",
+    "reading_wpm",
+    "theme",
+];
+
+/// `[extensions]` settings the webview may change, like UI_SETTINGS
+const UI_EXTENSION_SETTINGS: &[&str] = &["plantuml", "mermaid", "emoji", "csv", "wikilinks"];
+
+/// Check that a settings patch only names UI_SETTINGS and UI_EXTENSION_SETTINGS
+fn check_ui_settings(patch: &serde_json::Map<String, serde_json::Value>) -> Result<(), String> {
+    for (key, value) in patch {
+        let refused = if key == "extensions" {
+            // Resetting the whole table would reset the PlantUML settings too
+            match value {
+                serde_json::Value::Object(nested) => nested
+                    .keys()
+                    .find(|k| !UI_EXTENSION_SETTINGS.contains(&k.as_str()))
+                    .map(|k| format!("extensions.{}", k)),
+                _ => Some(key.clone()),
+            }
+        } else {
+            (!UI_SETTINGS.contains(&key.as_str())).then(|| key.clone())
+        };
+        if let Some(refused) = refused {
+            return Err(format!("{} can only be changed in config.toml", refused));
+        }
+    }
+    Ok(())
+}
+
+/// Merge a partial settings object into the config and save it, e.g.
+/// `{ "theme": "dark", "extensions": { "mermaid": true } }`. A null value resets that
+/// setting to its default. Only the keys in UI_SETTINGS and UI_EXTENSION_SETTINGS
+/// are accepted.
+#[tauri::command]
+fn update_config(
+    patch: serde_json::Map<String, serde_json::Value>,
+    state: tauri::State<AppState>,
+) -> Result<(), String> {
+    check_ui_settings(&patch)?;
+    let mut merged = match serde_json::to_value(state.current_config()) {
+        Ok(serde_json::Value::Object(current)) => current,
+        _ => serde_json::Map::new(),
+    };
+    config_patch::merge(&mut merged, &patch);
+    let config: AppConfig = serde_json::from_value(serde_json::Value::Object(merged))
+        .map_err(|e| format!("Invalid settings: {}", e))?;
+    persist_config(&config, &state)
+}
+
+/// Save config changes made by a settings command (refused in ephemeral mode)
+fn persist_config(config: &AppConfig, state: &AppState) -> Result<(), String> {
+    if state.ephemeral {
+        return Err("Settings are not saved in ephemeral mode".to_string());
+    }
+    config
+        .save()
+        .map_err(|e| format!("Failed to save config: {}", e))?;
+    *state.config.lock().unwrap_or_else(|e| e.into_inner()) = config.clone();
+    Ok(())
+}
+
+/// Delete persisted state files. `scope` is "window", "recent", "per_file", or "all";
+/// the config file is only removed for the explicit "config" scope.
+/// Returns the paths that were removed.
+#[tauri::command]
+fn reset_state(scope: String, state: tauri::State<AppState>) -> Result<Vec<String>, String> {
+    if state.ephemeral {
+        return Err("State is not modified in ephemeral mode".to_string());
+    }
+
+    let paths = match scope.as_str() {
+        "window" => vec![WindowState::config_path()],
+        "recent" => vec![RecentFiles::config_path()],
+        "per_file" => vec![ScrollPositions::config_path()],
+        "all" => vec![
+            WindowState::config_path(),
+            RecentFiles::config_path(),
+            ScrollPositions::config_path(),
+        ],
+        "config" => vec![AppConfig::resolve_config_path()],
+        other => return Err(format!("Unknown reset scope: {}", other)),
+    };
+
+    let mut removed = Vec::new();
+    for path in paths.into_iter().flatten() {
+        match fs::remove_file(&path) {
+            Ok(()) => removed.push(path.to_string_lossy().to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to remove {}: {}", path.display(), e)),
+        }
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+fn open_dropped_file(
+    path: String,
+    state: tauri::State<AppState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    load_file_into_state(&PathBuf::from(&path), &state, &app)
+}
+
+/// Copy the current file path to the clipboard, optionally relative to a base directory
+#[tauri::command]
+fn copy_file_path(
+    relative_to: Option<String>,
+    state: tauri::State<AppState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let file_path = state
+        .file_path
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if file_path.is_empty() {
+        return Err("No file is open".to_string());
+    }
+
+    let text = match relative_to {
+        Some(base) => pathdiff::diff_paths(&file_path, &base)
+            .map(|p| p.to_string_lossy().to_string())
+            .ok_or_else(|| format!("Cannot make {} relative to {}", file_path, base))?,
+        None => file_path,
+    };
+
+    app.clipboard()
+        .write_text(text.clone())
+        .map_err(|e| format!("Failed to write to clipboard: {}", e))?;
+
+    Ok(text)
+}
+
+/// Render `markdown` to an HTML fragment and put it on the clipboard, with a plain-text
+/// rendering alongside for targets that don't take HTML. Returns the HTML.
+fn copy_rendered_html(
+    state: &AppState,
+    app: &tauri::AppHandle,
+    markdown: &str,
+) -> Result<String, String> {
+    let html = render::render_markdown_to_html(markdown, &render_options(&state.current_config()));
+    app.clipboard()
+        .write_html(html.clone(), Some(plaintext::to_plain_text(markdown)))
+        .map_err(|e| format!("Failed to write to clipboard: {}", e))?;
+    Ok(html)
+}
+
+/// Copy one section, as listed by get_markdown_content, to the clipboard as rendered HTML
+#[tauri::command]
+fn copy_section_html(
+    section_index: usize,
+    state: tauri::State<AppState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let content = state
+        .content
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let sections = extract_sections(&content);
+    let section = sections.get(section_index).ok_or_else(|| {
+        format!(
+            "Section index {} out of range ({} sections)",
+            section_index,
+            sections.len()
+        )
+    })?;
+    copy_rendered_html(&state, &app, &section.content)
+}
+
+/// Copy the whole document, without front matter, to the clipboard as rendered HTML
+#[tauri::command]
+fn copy_document_html(
+    state: tauri::State<AppState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let content = state
+        .content
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if content.trim().is_empty() {
+        return Err("No document to copy".to_string());
+    }
+    let (_, body) = split_front_matter(&content);
+    copy_rendered_html(&state, &app, body)
+}
+
+/// Image referenced by the document, resolved against the file's directory
+#[derive(Clone, serde::Serialize)]
+struct ImageRef {
+    /// Raw src as written in the markdown
+    src: String,
+    /// Alt text
+    alt: String,
+    /// Line number of the reference (0-indexed)
+    line: usize,
+    /// Whether the src is a remote/data URL rather than a local file
+    is_remote: bool,
+    /// Resolved absolute path for local images inside the document's directory
+    resolved_path: Option<String>,
+    /// Whether the resolved local file exists
+    exists: bool,
+    /// File size in bytes for existing local images
+    size_bytes: Option<u64>,
+    /// Whether a local src points outside the document's directory. Nothing else is
+    /// reported for it, not even whether it exists.
+    outside_document: bool,
+}
+
+/// Whether a link target points outside the local filesystem
+fn is_remote_src(src: &str) -> bool {
+    let lower = src.to_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://") || lower.starts_with("data:")
+}
+
+/// Resolve a relative reference against a base directory, normalizing `.` and `..`
+/// (mirrors resolveRelativePath in the frontend)
+fn resolve_local_path(base_dir: &Path, relative: &str) -> PathBuf {
+    // Drop any query string or fragment
+    let relative = relative.split(['?', '#']).next().unwrap_or_default();
+    let joined = base_dir.join(relative);
+
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Resolve `relative` against `base` like resolve_local_path, then follow symlinks and
+/// check the file is really inside `base`. None if it doesn't exist or escapes `base`,
+/// whether through `..`, an absolute path, or a symlink pointing elsewhere.
+fn resolve_within(base: &Path, relative: &str) -> Option<PathBuf> {
+    let base = fs::canonicalize(base).ok()?;
+    let path = fs::canonicalize(resolve_local_path(&base, relative)).ok()?;
+    path.starts_with(&base).then_some(path)
+}
+
+/// An image file read by load_image
+#[derive(Clone, serde::Serialize)]
+struct ImageData {
+    mime: String,
+    /// The file's bytes, base64-encoded, for a `data:` URI
+    data: String,
+    /// Size in bytes
+    size: u64,
+}
+
+/// Read a local image for the current document, for webviews that can't load it
+/// through the asset protocol. `src` is resolved against the document's directory with
+/// resolve_within, so nothing outside that directory tree can be read.
+#[tauri::command]
+async fn load_image(src: String, state: tauri::State<'_, AppState>) -> Result<ImageData, String> {
+    if is_remote_src(&src) {
+        return Err(format!("Not a local image: {}", src));
+    }
+
+    let file_path = state
+        .file_path
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let max_size = state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .max_image_size();
+    let file_dir = Path::new(&file_path)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .ok_or_else(|| "No file is open to resolve images against".to_string())?;
+
+    let path = resolve_within(file_dir, &percent_decode(&src))
+        .ok_or_else(|| format!("Image not found in the document's directory: {}", src))?;
+
+    let mime = render::image_mime(&path).ok_or_else(|| format!("Not an image: {}", src))?;
+    let size = fs::metadata(&path)
+        .map_err(|e| format!("Failed to read {}: {}", src, e))?
+        .len();
+    if size > max_size {
+        return Err(format!(
+            "Image is too large ({} bytes, limit {}): {}",
+            size, max_size, src
+        ));
+    }
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", src, e))?;
+    Ok(ImageData {
+        mime: mime.to_string(),
+        data: render::base64_encode(&bytes),
+        size,
+    })
+}
+
+/// List all images in the current document with their resolved paths and existence
+#[tauri::command]
+fn list_images(state: tauri::State<AppState>) -> Vec<ImageRef> {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    let file_path = state.file_path.lock().unwrap_or_else(|e| e.into_inner());
+    let file_dir = PathBuf::from(file_path.as_str())
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    markdown::extract_links(&content)
+        .into_iter()
+        .filter(|link| link.is_image)
+        .map(|link| image_ref(&file_dir, link))
+        .collect()
+}
+
+/// Describe one image reference, looking only inside `file_dir` like load_image
+fn image_ref(file_dir: &Path, link: markdown::LinkRef) -> ImageRef {
+    let is_remote = is_remote_src(&link.target);
+    // Images outside the document's directory are flagged without touching the
+    // filesystem; inside it, a symlink out reads as missing
+    let outside_document =
+        !is_remote && !resolve_local_path(file_dir, &link.target).starts_with(file_dir);
+    let resolved = if is_remote || outside_document {
+        None
+    } else {
+        resolve_within(file_dir, &link.target)
+    };
+    let metadata = resolved.as_ref().and_then(|p| p.metadata().ok());
+
+    ImageRef {
+        src: link.target,
+        alt: link.text,
+        line: link.line,
+        is_remote,
+        resolved_path: resolved.map(|p| p.to_string_lossy().to_string()),
+        exists: metadata.is_some(),
+        size_bytes: metadata.map(|m| m.len()),
+        outside_document,
+    }
+}
+
+/// A numbered figure: an image with alt text or a caption line under it
+#[derive(Clone, serde::Serialize)]
+struct Figure {
+    caption: String,
+    src: String,
+    /// Line number of the image (0-indexed)
+    line: usize,
+    /// Sequential figure number, starting at 1
+    number: usize,
+}
+
+/// Build a table of figures. A caption line right below the image (`*Figure 1: ...*`
+/// or `Figure: ...`) wins over alt text; images with neither are treated as decorative.
+#[tauri::command]
+fn list_figures(state: tauri::State<AppState>) -> Vec<Figure> {
+    let content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+    let lines: Vec<&str> = content.lines().collect();
+
+    markdown::extract_links(&content)
+        .into_iter()
+        .filter(|link| link.is_image)
+        .filter_map(|link| {
+            let caption = lines
+                .get(link.line + 1)
+                .and_then(|line| caption_line(line))
+                .or_else(|| Some(plaintext::strip_inline(link.text.trim())))
+                .filter(|caption| !caption.is_empty())?;
+            Some((caption, link))
+        })
+        .enumerate()
+        .map(|(i, (caption, link))| Figure {
+            caption,
+            src: link.target,
+            line: link.line,
+            number: i + 1,
+        })
+        .collect()
+}
+
+/// Caption text if the line looks like a figure caption: fully emphasized, or
+/// starting with "Figure"/"Fig."
+fn caption_line(line: &str) -> Option<String> {
+    let trimmed = line.trim();
+    if trimmed.contains("![") {
+        return None;
+    }
+    let lower = trimmed.to_lowercase();
+    let is_emphasized = trimmed.len() > 2
+        && ['*', '_']
+            .iter()
+            .any(|c| trimmed.starts_with(*c) && trimmed.ends_with(*c));
+    if is_emphasized || lower.starts_with("figure") || lower.starts_with("fig.") {
+        Some(plaintext::strip_inline(trimmed))
+    } else {
+        None
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+struct MarkdownContent {
     content: String,
     file_path: String,
     file_name: String,
@@ -530,15 +4447,834 @@ struct MarkdownContent {
     extensions: ExtensionsConfig,
     /// Whether this is a PlantUML file (.puml, .plantuml)
     is_plantuml_file: bool,
+    /// Reduced-motion preference; None leaves detection to the frontend
+    reduce_motion: Option<bool>,
+    /// How the frontend should render this file
+    render_mode: RenderMode,
+    /// Parsed rows for CSV/TSV files (only when render_mode is table)
+    table: Vec<Vec<String>>,
+    /// Whether the table was cut off at the row/column caps
+    table_truncated: bool,
+    /// Whether the frontend should show the TOC sidebar
+    show_toc: bool,
+    /// Section to expand and scroll to on load (`--section`)
+    initial_section: Option<usize>,
+    /// Where to scroll on load (`file.md#anchor`, `file.md:42`)
+    scroll_to: Option<ScrollTarget>,
+    /// Max width of the rendered content as a CSS length
+    content_max_width: Option<String>,
+    /// Custom stylesheet to inject (`custom_css` / `--css`)
+    custom_css: Option<String>,
+    /// Line ranges of top-level `<details>` blocks
+    details_blocks: Vec<markdown::LineRange>,
+    /// Line ranges of pipe tables too wide to fit without scrolling
+    wide_tables: Vec<markdown::LineRange>,
+    /// Ordered lists that start at a number other than 1
+    ordered_list_starts: Vec<markdown::OrderedListStart>,
+    /// `- [ ]` / `- [x]` items, in the order their checkboxes render
+    task_items: Vec<markdown::TaskItem>,
+    /// ```plantuml / ```puml blocks (only when the plantuml extension is enabled)
+    plantuml_fences: Vec<markdown::CodeFence>,
+    /// ```mermaid blocks (only when the mermaid extension is enabled)
+    mermaid_fences: Vec<markdown::CodeFence>,
+    /// `[[Page]]` links, when the wikilinks extension is enabled
+    wikilinks: Vec<WikiLinkRef>,
+    /// Whether headings and TOC entries should be prefixed with their numbers
+    number_headings: bool,
+    /// Heading numbers in document order (empty unless `number_headings`)
+    heading_numbers: Vec<String>,
+    /// Parsed front matter key/values (only when front_matter_display is "table")
+    front_matter: Vec<FrontMatterEntry>,
+    /// Headings nested by level (only when is_large_file is true)
+    section_tree: Vec<SectionNode>,
+    /// Flat heading outline for a TOC, for files of any size
+    toc: Vec<OutlineEntry>,
+}
+
+/// Front matter key/value pair for the metadata card
+#[derive(Clone, serde::Serialize)]
+struct FrontMatterEntry {
+    key: String,
+    value: String,
+}
+
+/// Decide how a file is rendered, or None if glance doesn't open it; see
+/// `file_types::classify`
+fn classify_file(path: &Path, config: &AppConfig) -> Option<RenderMode> {
+    file_types::classify(path, config.extensions.csv, &config.extra_extensions)
+}
+
+#[derive(Clone)]
+struct AppState {
+    content: Arc<Mutex<String>>,
+    file_path: Arc<Mutex<String>>,
+    file_name: Arc<Mutex<String>>,
+    watcher_control: Arc<Mutex<Option<Sender<PathBuf>>>>,
+    is_large_file: Arc<Mutex<bool>>,
+    no_truncate: Arc<Mutex<bool>>,
+    /// Previously viewed files for link navigation, oldest first
+    history: Arc<Mutex<Vec<String>>>,
+    /// Last time a file was loaded or the window was shown/hidden (drives the idle timeout)
+    last_activity: Arc<Mutex<Instant>>,
+    /// Modification time of the current file when it was last read
+    last_seen_mtime: Arc<Mutex<Option<SystemTime>>>,
+    /// `--section` target, handed to the frontend once with the first payload
+    initial_section: Arc<Mutex<Option<usize>>>,
+    /// Open documents, in tab order; the active one is mirrored in the fields above
+    tabs: Arc<Mutex<Vec<Tab>>>,
+    /// Index of the shown tab in `tabs`
+    active_tab: Arc<Mutex<usize>>,
+    /// `#anchor` or `:line` target, handed to the frontend once with the next payload
+    open_target: Arc<Mutex<Option<OpenTarget>>>,
+    /// Whether the initially hidden window has been shown (by frontend_ready or timeout)
+    window_revealed: Arc<Mutex<bool>>,
+    /// Whether this instance owns the daemon endpoint (false for `--new-window`
+    /// instances started while a daemon is running)
+    serves_daemon: Arc<Mutex<bool>>,
+    /// Size in bytes above which files open in large-file mode (0 = never), from
+    /// `--large-file-threshold` or else config, see large_file_threshold()
+    large_file_threshold: Arc<Mutex<u64>>,
+    /// `--large-file-threshold`, taking precedence over `large_file_threshold` in config
+    threshold_override: Option<u64>,
+    /// Canonical paths opened with `--force`, allowed despite their extension. Only
+    /// this instance's own command line fills it (in run_app): neither the webview nor
+    /// daemon clients can widen the allowlist, so `--force` never goes to a daemon.
+    forced_files: Arc<Mutex<HashSet<PathBuf>>>,
+    /// Folder listed in the sidebar, when glance was opened on a directory
+    directory: Option<PathBuf>,
+    /// Config loaded at startup, kept current by settings commands and the config watcher
+    config: Arc<Mutex<AppConfig>>,
+    /// `--css` stylesheet, taking precedence over `custom_css` in config
+    css_override: Option<PathBuf>,
+    /// Contents of the custom stylesheet in use, if any
+    custom_css: Arc<Mutex<Option<String>>>,
+    /// Rendered PlantUML SVG, keyed by a hash of the backend and source
+    plantuml_cache: Arc<Mutex<HashMap<u64, String>>>,
+    /// PlantUML backend chosen from the config on first render (choosing scans PATH),
+    /// cleared when the PlantUML settings change
+    plantuml_backend: Arc<Mutex<Option<plantuml::Backend>>>,
+    /// Glance's last write to the current file, so the watcher can skip its events
+    expected_write: Arc<Mutex<Option<SelfWrite>>>,
+    /// `--ephemeral`: glance writes nothing to disk. Suppressed writes: window.json,
+    /// recent.json, config saves from settings commands, and the daemon socket
+    /// (the daemon is skipped entirely).
+    ephemeral: bool,
+}
+
+/// A write glance made to the current file: what it wrote, and when
+#[derive(Clone, Copy)]
+struct SelfWrite {
+    hash: u64,
+    at: Instant,
+}
+
+impl SelfWrite {
+    /// Whether `content` read back from disk is this write, made within SELF_WRITE_WINDOW.
+    /// An external edit in the meantime reads back different content, so it still reloads.
+    fn matches(&self, content: &str, now: Instant) -> bool {
+        now.duration_since(self.at) < SELF_WRITE_WINDOW && content_hash(content) == self.hash
+    }
+}
+
+impl AppState {
+    /// Reset the daemon idle timer
+    fn mark_active(&self) {
+        *self.last_activity.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    /// Save `content` to the current file on glance's behalf, updating the in-memory
+    /// copy, so the watcher events it causes don't reload the view
+    fn write_document(&self, path: &Path, content: String) -> std::io::Result<()> {
+        let expected = SelfWrite {
+            hash: content_hash(&content),
+            at: Instant::now(),
+        };
+        *self
+            .expected_write
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(expected);
+        if let Err(e) = fs::write(path, &content) {
+            *self
+                .expected_write
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()) = None;
+            return Err(e);
+        }
+
+        *self.content.lock().unwrap_or_else(|e| e.into_inner()) = content;
+        *self
+            .last_seen_mtime
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = file_mtime(path);
+        Ok(())
+    }
+
+    /// Whether `content` just read from disk is glance's own recent write
+    fn is_self_write(&self, content: &str) -> bool {
+        self.expected_write
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some_and(|write| write.matches(content, Instant::now()))
+    }
+
+    /// Config as of startup or the last edit the config watcher saw, without a disk read
+    fn current_config(&self) -> AppConfig {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Size in bytes above which files open in large-file mode (0 = never)
+    fn large_file_threshold(&self) -> u64 {
+        *self
+            .large_file_threshold
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Modification time of a file, if it can be read
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Hash of document content, used to skip reloads that change nothing.
+/// Masked to 53 bits so it survives a round trip through a JavaScript number.
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish() & ((1 << 53) - 1)
+}
+
+/// Re-read the current file from disk, emitting `file-changed` only if the content differs
+fn reload_from_disk(state: &AppState, app_handle: &tauri::AppHandle) {
+    let path = state
+        .file_path
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let Ok(new_content) = fs::read_to_string(&path) else {
+        return;
+    };
+    if new_content.trim().is_empty() {
+        return;
+    }
+
+    *state
+        .last_seen_mtime
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = file_mtime(Path::new(&path));
+    if state.is_self_write(&new_content) {
+        return;
+    }
+
+    let is_large_file = *state
+        .is_large_file
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let section_changes = {
+        let mut content = state.content.lock().unwrap_or_else(|e| e.into_inner());
+        if content_hash(&content) == content_hash(&new_content) {
+            return;
+        }
+        // In large-file mode, diff against the sections of the content being replaced
+        let changes = if is_large_file {
+            diff_sections(&extract_sections(&content), &extract_sections(&new_content))
+        } else {
+            None
+        };
+        *content = new_content;
+        changes
+    };
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        match section_changes {
+            Some(changes) => {
+                let _ = window.emit("section-changed", changes);
+            }
+            None => {
+                let _ = window.emit("file-changed", ());
+            }
+        }
+    }
+}
+
+/// Directory watched for changes to `path` (its parent)
+fn watch_dir(path: &Path) -> &Path {
+    path.parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(path)
+}
+
+/// Whether a directory watch event changes `path` (modify, create or remove; renames
+/// count as modify). Matched by file name since the watch is non-recursive.
+fn is_change_to(event: &Event, path: &Path) -> bool {
+    let Some(name) = path.file_name() else {
+        return false;
+    };
+    matches!(
+        event.kind,
+        notify::EventKind::Modify(_) | notify::EventKind::Create(_) | notify::EventKind::Remove(_)
+    ) && event
+        .paths
+        .iter()
+        .any(|event_path| event_path.file_name() == Some(name))
+}
+
+/// Emit `directory-changed` when files are added, removed, or renamed anywhere under
+/// `dir`, once per burst of events
+fn start_directory_watcher(dir: PathBuf, app_handle: tauri::AppHandle) {
+    thread::spawn(move || {
+        let (event_tx, event_rx) = channel();
+        let mut watcher = match RecommendedWatcher::new(
+            move |res: Result<Event, notify::Error>| {
+                if let Ok(event) = res {
+                    let _ = event_tx.send(event);
+                }
+            },
+            Config::default(),
+        ) {
+            Ok(w) => w,
+            Err(e) => {
+                eprintln!("Failed to create directory watcher: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = watcher.watch(&dir, RecursiveMode::Recursive) {
+            eprintln!("Failed to watch directory {}: {}", dir.display(), e);
+            return;
+        }
+
+        while let Ok(event) = event_rx.recv() {
+            if !changes_file_list(&event) {
+                continue;
+            }
+            // Refresh once per burst (e.g. a git checkout), but not never if a file
+            // in the tree is written continuously
+            let deadline = Instant::now() + DIRECTORY_EVENT_MAX_DELAY;
+            while Instant::now() < deadline
+                && event_rx.recv_timeout(DIRECTORY_EVENT_DEBOUNCE).is_ok()
+            {}
+
+            if let Some(window) = app_handle.get_webview_window("main") {
+                if let Err(e) = window.emit("directory-changed", ()) {
+                    eprintln!("Failed to emit directory-changed event: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Reload the config when a config file in the config directory is written, created,
+/// or removed, then apply it and emit `config-changed`. The directory is watched
+/// rather than the file so a config created after launch is picked up. The custom
+/// stylesheet's directory is watched too, re-emitting it with `css-changed` on edits.
+fn start_config_watcher(state: AppState, app_handle: tauri::AppHandle) {
+    let Some(config_dir) =
+        ProjectDirs::from("com", "glance", "glance").map(|dirs| dirs.config_dir().to_path_buf())
+    else {
+        return;
+    };
+    // Watching needs the directory to exist; ephemeral mode writes nothing, so it
+    // only watches one that already does
+    if !state.ephemeral {
+        let _ = fs::create_dir_all(&config_dir);
+    }
+
+    thread::spawn(move || {
+        let (event_tx, event_rx) = channel();
+        let mut watcher = match RecommendedWatcher::new(
+            move |res: Result<Event, notify::Error>| {
+                if let Ok(event) = res {
+                    let _ = event_tx.send(event);
+                }
+            },
+            Config::default(),
+        ) {
+            Ok(w) => w,
+            Err(e) => {
+                eprintln!("Failed to create config watcher: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = watcher.watch(&config_dir, RecursiveMode::NonRecursive) {
+            eprintln!(
+                "Failed to watch config directory {}: {}",
+                config_dir.display(),
+                e
+            );
+            return;
+        }
+
+        let mut current = state.current_config();
+        let mut css_path = custom_css_path(&state);
+        let mut css_dir = None;
+        watch_css_dir(&mut watcher, &config_dir, &mut css_dir, css_path.as_deref());
+
+        while let Ok(event) = event_rx.recv() {
+            let mut touches_config = touches_config_file(&event);
+            let mut touches_css = touches_file(&event, css_path.as_deref());
+            if !touches_config && !touches_css {
+                continue;
+            }
+            while let Ok(event) = event_rx.recv_timeout(CONFIG_EVENT_DEBOUNCE) {
+                touches_config |= touches_config_file(&event);
+                touches_css |= touches_file(&event, css_path.as_deref());
+            }
+
+            if touches_config {
+                let config = AppConfig::load();
+                apply_config_change(&current, &config, &state, &app_handle);
+                current = config;
+
+                // The config may now point at a different stylesheet
+                let new_css_path = custom_css_path(&state);
+                if new_css_path != css_path {
+                    css_path = new_css_path;
+                    watch_css_dir(&mut watcher, &config_dir, &mut css_dir, css_path.as_deref());
+                    touches_css = true;
+                }
+            }
+            if touches_css {
+                reload_custom_css(&state, &app_handle, css_path.as_deref());
+            }
+        }
+    });
+}
+
+/// Whether an event touches config.toml (or its JSON/YAML alternatives)
+fn touches_config_file(event: &Event) -> bool {
+    event.paths.iter().any(|path| {
+        path.file_name()
+            .is_some_and(|name| CONFIG_FILE_NAMES.iter().any(|n| name == *n))
+    })
+}
+
+/// Whether an event touches `path`
+fn touches_file(event: &Event, path: Option<&Path>) -> bool {
+    path.is_some_and(|path| event.paths.iter().any(|p| p == path))
+}
+
+/// Move the stylesheet watch to the directory of `css_path`. The config directory is
+/// always watched, so it's never watched twice or unwatched here.
+fn watch_css_dir(
+    watcher: &mut RecommendedWatcher,
+    config_dir: &Path,
+    css_dir: &mut Option<PathBuf>,
+    css_path: Option<&Path>,
+) {
+    let new_dir = css_path
+        .and_then(Path::parent)
+        .filter(|dir| *dir != config_dir)
+        .map(Path::to_path_buf);
+    if new_dir == *css_dir {
+        return;
+    }
+    if let Some(old) = css_dir.take() {
+        let _ = watcher.unwatch(&old);
+    }
+    if let Some(dir) = new_dir {
+        match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            Ok(()) => *css_dir = Some(dir),
+            Err(e) => eprintln!(
+                "Failed to watch stylesheet directory {}: {}",
+                dir.display(),
+                e
+            ),
+        }
+    }
+}
+
+/// The stylesheet in use: `--css`, else `custom_css` from config
+fn custom_css_path(state: &AppState) -> Option<PathBuf> {
+    state.css_override.clone().or_else(|| {
+        state
+            .config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .custom_css_path()
+    })
+}
+
+/// Read a custom stylesheet, warning rather than failing when it can't be read
+fn read_custom_css(path: &Path) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(css) => Some(css),
+        Err(e) => {
+            eprintln!("Warning: Ignoring custom CSS {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Re-read the custom stylesheet, emitting `css-changed` with it if it changed
+fn reload_custom_css(state: &AppState, app_handle: &tauri::AppHandle, path: Option<&Path>) {
+    let css = path.and_then(read_custom_css);
+    {
+        let mut current = state.custom_css.lock().unwrap_or_else(|e| e.into_inner());
+        if *current == css {
+            return;
+        }
+        current.clone_from(&css);
+    }
+    if let Some(window) = app_handle.get_webview_window("main") {
+        if let Err(e) = window.emit("css-changed", css) {
+            eprintln!("Failed to emit css-changed event: {}", e);
+        }
+    }
+}
+
+/// Bring the running app in line with an edited config. Settings read on demand
+/// take effect when the frontend re-fetches on `config-changed`.
+fn apply_config_change(
+    previous: &AppConfig,
+    config: &AppConfig,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+) {
+    // Saves that change nothing, e.g. an editor touching the file
+    if serde_json::to_value(previous).ok() == serde_json::to_value(config).ok() {
+        return;
+    }
+    eprintln!("Config changed, reloading");
+    *state.config.lock().unwrap_or_else(|e| e.into_inner()) = config.clone();
+
+    // Choose the PlantUML backend again on the next render
+    if config.extensions.plantuml_jar != previous.extensions.plantuml_jar
+        || config.extensions.plantuml_server != previous.extensions.plantuml_server
+    {
+        *state
+            .plantuml_backend
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    if state.threshold_override.is_none() {
+        *state
+            .large_file_threshold
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = config.large_file_threshold();
+    }
+
+    // Only a changed setting overrides a runtime toggle (set_no_truncate, --no-truncate)
+    if config.no_truncate != previous.no_truncate {
+        *state.no_truncate.lock().unwrap_or_else(|e| e.into_inner()) = config.no_truncate;
+    }
+    let size = state
+        .content
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .len() as u64;
+    let no_truncate = *state.no_truncate.lock().unwrap_or_else(|e| e.into_inner());
+    *state
+        .is_large_file
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) =
+        exceeds_threshold(size, state.large_file_threshold()) && !no_truncate;
+
+    let Some(window) = app_handle.get_webview_window("main") else {
+        return;
+    };
+    if config.theme != previous.theme {
+        apply_theme(&window, config.theme);
+    }
+    if let Err(e) = window.emit("config-changed", config) {
+        eprintln!("Failed to emit config-changed event: {}", e);
+    }
+}
+
+/// Whether an event can add, remove, or rename a listed file (content edits can't)
+fn changes_file_list(event: &Event) -> bool {
+    matches!(
+        event.kind,
+        notify::EventKind::Create(_)
+            | notify::EventKind::Remove(_)
+            | notify::EventKind::Modify(notify::event::ModifyKind::Name(_))
+    )
+}
+
+/// React to the current file being deleted, per `on_delete`
+fn handle_file_deleted(state: &AppState, app_handle: &tauri::AppHandle) {
+    match state.current_config().on_delete() {
+        OnDelete::Keep => {}
+        OnDelete::Prev => {
+            // Most recent history entry that still exists
+            let previous = {
+                let mut history = state.history.lock().unwrap_or_else(|e| e.into_inner());
+                std::iter::from_fn(|| history.pop()).find(|path| Path::new(path).exists())
+            };
+            if let Some(previous) = previous {
+                match load_file_into_state(Path::new(&previous), state, app_handle) {
+                    Ok(_) => {
+                        show_main_window(app_handle);
+                        return;
+                    }
+                    Err(e) => eprintln!("Failed to open previous file: {}", e),
+                }
+            }
+        }
+        OnDelete::Close => {
+            if let Some(window) = app_handle.get_webview_window("main") {
+                state.mark_active();
+                let _ = window.hide();
+            }
+            return;
+        }
+    }
+
+    // Keep showing the stale content and let the frontend flag it
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.emit("file-deleted", ());
+    }
+}
+
+/// Payload of `section-changed`: section indices affected by an edit in large-file mode
+#[derive(Clone, serde::Serialize)]
+struct SectionChanges {
+    /// Indices (in the new list) whose content changed
+    changed: Vec<usize>,
+    /// Indices (in the new list) of inserted sections
+    added: Vec<usize>,
+    /// Indices (in the old list) of deleted sections
+    removed: Vec<usize>,
+}
+
+/// Compare two section extractions by per-section content hash. Unchanged sections are
+/// matched from both ends; returns None when the edited span covers more than half the
+/// document or no section differs, where a full reload is simpler.
+fn diff_sections(old: &[MarkdownSection], new: &[MarkdownSection]) -> Option<SectionChanges> {
+    let old_hashes: Vec<u64> = old.iter().map(|s| content_hash(&s.content)).collect();
+    let new_hashes: Vec<u64> = new.iter().map(|s| content_hash(&s.content)).collect();
+
+    let prefix = old_hashes
+        .iter()
+        .zip(&new_hashes)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_hashes[prefix..]
+        .iter()
+        .rev()
+        .zip(new_hashes[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_span = prefix..old_hashes.len() - suffix;
+    let new_span = prefix..new_hashes.len() - suffix;
+    // Nothing matched to a section (e.g. text before the first heading changed)
+    if old_span.is_empty() && new_span.is_empty() {
+        return None;
+    }
+    if old_span.len().max(new_span.len()) * 2 > new_hashes.len().max(1) {
+        return None;
+    }
+
+    // Pair up the edited span; the excess on either side was added or removed
+    let paired = old_span.len().min(new_span.len());
+    Some(SectionChanges {
+        changed: (prefix..prefix + paired).collect(),
+        added: (prefix + paired..new_span.end).collect(),
+        removed: (prefix + paired..old_span.end).collect(),
+    })
+}
+
+/// Show the window that starts hidden, once: later calls do nothing so a window the
+/// user has since hidden stays hidden
+fn reveal_window_once(state: &AppState, app_handle: &tauri::AppHandle) {
+    let mut revealed = state
+        .window_revealed
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if !*revealed {
+        *revealed = true;
+        raise_main_window(app_handle);
+    }
+}
+
+/// Called by the frontend after its first render; shows the window on cold start
+#[tauri::command]
+fn frontend_ready(state: tauri::State<AppState>, app: tauri::AppHandle) {
+    reveal_window_once(&state, &app);
+}
+
+/// Remove the daemon socket file, if this instance is the one serving it
+fn remove_socket_file(state: &AppState) {
+    if *state
+        .serves_daemon
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+    {
+        ipc::default_transport().cleanup();
+    }
+}
+
+/// Tray icon for the daemon, so a hidden window can be shown again and the process
+/// quit without hunting for it
+fn create_tray(app_handle: &tauri::AppHandle) -> tauri::Result<()> {
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Glance")
+        .menu(&tray_menu(app_handle)?)
+        .on_menu_event(|app, event| handle_tray_menu(app, event.id().as_ref()));
+    if let Some(icon) = app_handle.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app_handle)?;
+    Ok(())
+}
+
+/// Show Window, Open File…, Recent (from recent.json) and Quit
+fn tray_menu(app_handle: &tauri::AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let recent_items = RecentFiles::load()
+        .entries
+        .iter()
+        .take(TRAY_RECENT_FILES)
+        .enumerate()
+        .map(|(index, entry)| {
+            let path = Path::new(&entry.path);
+            let label = match (path.file_name(), path.parent()) {
+                (Some(name), Some(dir)) => {
+                    format!("{} — {}", name.to_string_lossy(), dir.display())
+                }
+                _ => entry.path.clone(),
+            };
+            MenuItem::with_id(
+                app_handle,
+                format!("recent:{}", index),
+                label,
+                true,
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let recent_refs: Vec<&dyn IsMenuItem<tauri::Wry>> = recent_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<tauri::Wry>)
+        .collect();
+    let recent = Submenu::with_id_and_items(
+        app_handle,
+        "recent",
+        "Recent",
+        !recent_refs.is_empty(),
+        &recent_refs,
+    )?;
+
+    Menu::with_items(
+        app_handle,
+        &[
+            &MenuItem::with_id(app_handle, "show", "Show Window", true, None::<&str>)?,
+            &MenuItem::with_id(app_handle, "open", "Open File…", true, None::<&str>)?,
+            &recent,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &MenuItem::with_id(app_handle, "quit", "Quit", true, None::<&str>)?,
+        ],
+    )
+}
+
+/// Rebuild the tray menu so Recent matches recent.json. Does nothing without a tray.
+fn refresh_tray_menu(app_handle: &tauri::AppHandle) {
+    let Some(tray) = app_handle.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match tray_menu(app_handle) {
+        Ok(menu) => {
+            if let Err(e) = tray.set_menu(Some(menu)) {
+                eprintln!("Failed to update tray menu: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to build tray menu: {}", e),
+    }
+}
+
+fn handle_tray_menu(app_handle: &tauri::AppHandle, id: &str) {
+    let state = app_handle.state::<AppState>();
+    match id {
+        "show" => {
+            state.mark_active();
+            raise_main_window(app_handle);
+        }
+        "open" => {
+            let app_handle = app_handle.clone();
+            app_handle
+                .dialog()
+                .file()
+                .add_filter("Markdown", file_types::MARKDOWN_EXTENSIONS)
+                .add_filter("AsciiDoc", &["adoc", "asciidoc"])
+                .pick_file(move |selected| {
+                    let Some(path) = selected.and_then(|path| path.into_path().ok()) else {
+                        return;
+                    };
+                    let state = app_handle.state::<AppState>();
+                    match load_file_into_state(&path, &state, &app_handle) {
+                        Ok(_) => show_main_window(&app_handle),
+                        Err(e) => {
+                            raise_main_window(&app_handle);
+                            emit_load_error(&app_handle, &e);
+                        }
+                    }
+                });
+        }
+        "quit" => {
+            remove_socket_file(&state);
+            app_handle.exit(0);
+        }
+        _ => {
+            let Some(index) = id
+                .strip_prefix("recent:")
+                .and_then(|index| index.parse().ok())
+            else {
+                return;
+            };
+            if let Err(e) = open_recent(index, &state, app_handle) {
+                raise_main_window(app_handle);
+                emit_load_error(app_handle, &e);
+            }
+        }
+    }
 }
 
-struct AppState {
-    content: Arc<Mutex<String>>,
-    file_path: Arc<Mutex<String>>,
-    file_name: Arc<Mutex<String>>,
-    watcher_control: Arc<Mutex<Option<Sender<PathBuf>>>>,
-    is_large_file: Arc<Mutex<bool>>,
-    no_truncate: Arc<Mutex<bool>>,
+/// Exit cleanly on SIGTERM or SIGINT, removing the daemon socket, instead of being
+/// killed with the socket file left behind
+#[cfg(unix)]
+fn start_signal_handler(state: AppState, app_handle: tauri::AppHandle) {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+
+    let mut signals = match signal_hook::iterator::Signals::new([SIGTERM, SIGINT]) {
+        Ok(signals) => signals,
+        Err(e) => {
+            eprintln!("Failed to install signal handler: {}", e);
+            return;
+        }
+    };
+    thread::spawn(move || {
+        if signals.forever().next().is_some() {
+            remove_socket_file(&state);
+            app_handle.exit(0);
+        }
+    });
+}
+
+/// Exit the daemon once the window has stayed hidden for `timeout` with no activity
+fn start_idle_timer(timeout: Duration, state: AppState, app_handle: tauri::AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(IDLE_CHECK_INTERVAL);
+
+        let visible = app_handle
+            .get_webview_window("main")
+            .and_then(|window| window.is_visible().ok())
+            .unwrap_or(false);
+        let idle_for = state
+            .last_activity
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .elapsed();
+
+        if !visible && idle_for >= timeout {
+            eprintln!("Daemon idle for {}s, exiting", idle_for.as_secs());
+            remove_socket_file(&state);
+            app_handle.exit(0);
+            return;
+        }
+    });
 }
 
 /// Extract sections from markdown content based on headings
@@ -546,8 +5282,20 @@ fn extract_sections(content: &str) -> Vec<MarkdownSection> {
     let lines: Vec<&str> = content.lines().collect();
     let mut sections: Vec<MarkdownSection> = Vec::new();
     let mut in_code_block = false;
+    let mut skip_underline = false;
+    let mut seen_slugs = HashMap::new();
+
+    // Front matter is metadata, not a heading or part of the introduction
+    let fm = front_matter::parse_front_matter(content);
+    let body_start = fm.as_ref().map_or(0, |fm| fm.line_count);
+
+    for (line_num, line) in lines.iter().enumerate().skip(body_start) {
+        // The underline of a setext heading already recorded
+        if skip_underline {
+            skip_underline = false;
+            continue;
+        }
 
-    for (line_num, line) in lines.iter().enumerate() {
         // Track code block state to ignore headings inside code blocks
         if line.starts_with("```") || line.starts_with("~~~") {
             in_code_block = !in_code_block;
@@ -560,12 +5308,36 @@ fn extract_sections(content: &str) -> Vec<MarkdownSection> {
 
         // Check for ATX-style headings (# Heading)
         if let Some(heading_match) = parse_heading(line) {
+            let anchor = slug::slugify(&plaintext::strip_inline(&heading_match.1), &mut seen_slugs);
             sections.push(MarkdownSection {
                 level: heading_match.0,
                 title: heading_match.1,
                 content: String::new(), // Will be filled in later
                 start_line: line_num,
+                number: None,
+                anchor,
             });
+            continue;
+        }
+
+        // Check for setext-style headings (text underlined with === or ---). The text
+        // line must be plain and non-blank, so a lone `---` stays a horizontal rule.
+        let level = lines
+            .get(line_num + 1)
+            .and_then(|next| parse_setext_underline(next));
+        if let Some(level) = level {
+            if can_be_setext_text(line) {
+                let title = line.trim().to_string();
+                sections.push(MarkdownSection {
+                    level,
+                    anchor: slug::slugify(&plaintext::strip_inline(&title), &mut seen_slugs),
+                    title,
+                    content: String::new(),
+                    start_line: line_num,
+                    number: None,
+                });
+                skip_underline = true;
+            }
         }
     }
 
@@ -582,8 +5354,8 @@ fn extract_sections(content: &str) -> Vec<MarkdownSection> {
     }
 
     // If there's content before the first heading, add it as an intro section
-    if !sections.is_empty() && sections[0].start_line > 0 {
-        let intro_content = lines[0..sections[0].start_line].join("\n");
+    if !sections.is_empty() && sections[0].start_line > body_start {
+        let intro_content = lines[body_start..sections[0].start_line].join("\n");
         if !intro_content.trim().is_empty() {
             sections.insert(
                 0,
@@ -591,25 +5363,148 @@ fn extract_sections(content: &str) -> Vec<MarkdownSection> {
                     level: 0,
                     title: "Introduction".to_string(),
                     content: intro_content,
-                    start_line: 0,
+                    start_line: body_start,
+                    number: None,
+                    anchor: String::new(),
                 },
             );
         }
     }
 
-    // If no sections found, return a single section with all content
+    // If no sections found, return a single section with all content after the front matter
     if sections.is_empty() {
         sections.push(MarkdownSection {
             level: 0,
             title: "Document".to_string(),
-            content: content.to_string(),
-            start_line: 0,
+            content: fm
+                .as_ref()
+                .map_or(content, |fm| front_matter::body(content, fm))
+                .to_string(),
+            start_line: body_start,
+            number: None,
+            anchor: String::new(),
         });
     }
 
     sections
 }
 
+/// Fill in hierarchical heading numbers; the synthetic level-0 sections stay unnumbered
+fn number_sections(sections: &mut [MarkdownSection]) {
+    let levels: Vec<u8> = sections
+        .iter()
+        .map(|s| s.level)
+        .filter(|level| *level > 0)
+        .collect();
+    let mut numbers = markdown::number_headings(&levels).into_iter();
+    for section in sections.iter_mut().filter(|s| s.level > 0) {
+        section.number = numbers.next();
+    }
+}
+
+/// Heading entry in the document outline
+#[derive(Clone, serde::Serialize)]
+struct OutlineEntry {
+    /// Heading level (1-6)
+    level: u8,
+    /// Heading title text
+    title: String,
+    /// Line number of the heading (0-indexed)
+    line: usize,
+}
+
+/// Lightweight heading scan without splitting section content
+fn extract_outline(content: &str) -> Vec<OutlineEntry> {
+    let mut outline = Vec::new();
+    let mut in_code_block = false;
+
+    for (line_num, line) in content.lines().enumerate() {
+        if markdown::is_fence_line(line) {
+            in_code_block = !in_code_block;
+            continue;
+        }
+
+        if in_code_block {
+            continue;
+        }
+
+        if let Some((level, title)) = parse_heading(line) {
+            outline.push(OutlineEntry {
+                level,
+                title,
+                line: line_num,
+            });
+        }
+    }
+
+    outline
+}
+
+/// Node in the nested section tree, with headings nested under lower levels
+#[derive(Clone, serde::Serialize)]
+struct SectionNode {
+    /// Stable id (the heading's slug anchor)
+    id: String,
+    /// Heading level (1-6)
+    level: u8,
+    /// Heading title text
+    title: String,
+    /// Nesting depth in the tree (0 = top level)
+    depth: usize,
+    /// Line number of the heading (0-indexed)
+    start_line: usize,
+    children: Vec<SectionNode>,
+}
+
+/// Nest outline entries by heading level; skipped levels nest under the nearest shallower heading
+fn build_section_tree(outline: &[OutlineEntry]) -> Vec<SectionNode> {
+    fn build(
+        outline: &[OutlineEntry],
+        ids: &[String],
+        i: &mut usize,
+        parent_level: u8,
+        depth: usize,
+    ) -> Vec<SectionNode> {
+        let mut nodes = Vec::new();
+        while *i < outline.len() && outline[*i].level > parent_level {
+            let entry = &outline[*i];
+            let id = ids[*i].clone();
+            *i += 1;
+            let children = build(outline, ids, i, entry.level, depth + 1);
+            nodes.push(SectionNode {
+                id,
+                level: entry.level,
+                title: entry.title.clone(),
+                depth,
+                start_line: entry.line,
+                children,
+            });
+        }
+        nodes
+    }
+
+    build(outline, &heading_slugs(outline), &mut 0, 0, 0)
+}
+
+/// GitHub-style anchors for each outline entry, slugged from the heading's plain text
+fn heading_slugs(outline: &[OutlineEntry]) -> Vec<String> {
+    let mut seen = HashMap::new();
+    outline
+        .iter()
+        .map(|entry| slug::slugify(&plaintext::strip_inline(&entry.title), &mut seen))
+        .collect()
+}
+
+/// Section ids in document order (pre-order walk of the tree)
+fn all_section_ids(tree: &[SectionNode]) -> Vec<String> {
+    let mut ids = Vec::new();
+    for node in tree {
+        ids.push(node.id.clone());
+        ids.extend(all_section_ids(&node.children));
+    }
+    ids
+}
+
 /// Parse a heading line and return (level, title)
 fn parse_heading(line: &str) -> Option<(u8, String)> {
     let trimmed = line.trim();
@@ -629,19 +5524,158 @@ fn parse_heading(line: &str) -> Option<(u8, String)> {
     None
 }
 
+/// Level of a setext heading underline: 1 for `===`, 2 for `---` (up to 3 spaces of indent)
+fn parse_setext_underline(line: &str) -> Option<u8> {
+    let body = line.trim_end();
+    let trimmed = body.trim_start();
+    if body.len() - trimmed.len() > 3 || trimmed.is_empty() {
+        return None;
+    }
+    if trimmed.chars().all(|c| c == '=') {
+        Some(1)
+    } else if trimmed.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Whether a line can be the text of a setext heading: non-blank, not indented code,
+/// and not itself a heading, horizontal rule, fence, list item, or blockquote
+fn can_be_setext_text(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() || line.len() - trimmed.len() > 3 {
+        return false;
+    }
+    let compact: String = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+    let is_rule = compact.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|marker| compact.chars().all(|c| c == *marker));
+    let is_list_item = (trimmed.starts_with(['-', '*', '+'])
+        && trimmed[1..].starts_with([' ', '\t']))
+        || markdown::ordered_item_number(trimmed).is_some();
+    !(is_rule
+        || is_list_item
+        || parse_setext_underline(line).is_some()
+        || trimmed.starts_with('>')
+        || trimmed.starts_with("```")
+        || trimmed.starts_with("~~~"))
+}
+
+/// Persist the window's current position and size
+fn save_window_state(window: &tauri::Window) {
+    // A minimized window reports a degenerate size/position
+    if window.is_minimized().unwrap_or(false) {
+        return;
+    }
+    let maximized = window.is_maximized().unwrap_or(false);
+    let fullscreen = window.is_fullscreen().unwrap_or(false);
+
+    let state = if maximized || fullscreen {
+        // The current geometry is the whole screen; keep the last normal geometry
+        // so un-maximizing after a restart returns to it
+        WindowState {
+            maximized,
+            fullscreen,
+            ..WindowState::load()
+        }
+    } else {
+        let (Ok(position), Ok(size)) = (window.outer_position(), window.outer_size()) else {
+            return;
+        };
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        WindowState {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+            maximized: false,
+            fullscreen: false,
+        }
+    };
+    if let Err(e) = state.save() {
+        eprintln!("Failed to save window state: {}", e);
+    }
+}
+
+/// Save the window state after a move/resize settles. Events arriving while a save
+/// is pending are folded into it, so at most one write happens per debounce period.
+fn schedule_window_state_save(window: &tauri::Window, pending: &Arc<Mutex<bool>>) {
+    {
+        let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+        if *pending {
+            return;
+        }
+        *pending = true;
+    }
+
+    let window = window.clone();
+    let pending = pending.clone();
+    thread::spawn(move || {
+        thread::sleep(WINDOW_SAVE_DEBOUNCE);
+        *pending.lock().unwrap_or_else(|e| e.into_inner()) = false;
+        save_window_state(&window);
+    });
+}
+
 fn run_app(
     file_path: String,
     file_name: String,
     content: String,
     is_large_file: bool,
-    no_truncate: bool,
+    config: AppConfig,
+    options: StartupOptions,
 ) {
+    let StartupOptions {
+        no_truncate,
+        command_source,
+        ephemeral,
+        daemon,
+        initial_section,
+        large_file_threshold,
+        threshold_override,
+        open_target,
+        extra_files,
+        remote_listen,
+        force,
+        directory,
+        custom_css,
+    } = options;
     let window_title = if file_name == "Glance" {
         "Glance".to_string()
     } else {
         format!("{} - Glance", file_name)
     };
     let has_initial_file = !file_path.is_empty();
+
+    // The initial file is the active tab; further files open as background tabs
+    let mut tabs = Vec::new();
+    if has_initial_file {
+        tabs.push(Tab {
+            path: file_path.clone(),
+            name: file_name.clone(),
+        });
+    }
+    tabs.extend(extra_files.into_iter().map(|path| {
+        Tab {
+            name: Path::new(&path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone()),
+            path,
+        }
+    }));
+
+    // --force applies to exactly the files named on the command line
+    let forced_files: HashSet<PathBuf> = if force {
+        tabs.iter().map(|tab| PathBuf::from(&tab.path)).collect()
+    } else {
+        HashSet::new()
+    };
+
     let content = Arc::new(Mutex::new(content));
     let file_path_state = Arc::new(Mutex::new(file_path.clone()));
     let file_name_state = Arc::new(Mutex::new(file_name));
@@ -652,61 +5686,203 @@ fn run_app(
 
     let watcher_control_for_setup = watcher_control.clone();
 
+    if let Some(path) = AppConfig::resolve_config_path() {
+        eprintln!("Loaded config from {}", path.display());
+    }
+
     // Load saved window state
     let saved_state = WindowState::load();
 
-    // Create clones for socket server thread
-    let content_for_socket = content.clone();
-    let file_path_for_socket = file_path_state.clone();
-    let file_name_for_socket = file_name_state.clone();
-    let is_large_file_for_socket = is_large_file_state.clone();
-    let no_truncate_for_socket = no_truncate_state.clone();
-    let watcher_control_for_socket = watcher_control.clone();
+    let app_state = AppState {
+        content: content.clone(),
+        file_path: file_path_state.clone(),
+        file_name: file_name_state.clone(),
+        watcher_control: watcher_control.clone(),
+        is_large_file: is_large_file_state,
+        no_truncate: no_truncate_state,
+        history: Arc::new(Mutex::new(Vec::new())),
+        last_activity: Arc::new(Mutex::new(Instant::now())),
+        last_seen_mtime: Arc::new(Mutex::new(file_mtime(&watch_path))),
+        initial_section: Arc::new(Mutex::new(initial_section)),
+        tabs: Arc::new(Mutex::new(tabs)),
+        active_tab: Arc::new(Mutex::new(0)),
+        open_target: Arc::new(Mutex::new(open_target)),
+        window_revealed: Arc::new(Mutex::new(false)),
+        serves_daemon: Arc::new(Mutex::new(false)),
+        large_file_threshold: Arc::new(Mutex::new(large_file_threshold)),
+        threshold_override,
+        forced_files: Arc::new(Mutex::new(forced_files)),
+        directory: directory.clone(),
+        config: Arc::new(Mutex::new(config.clone())),
+        custom_css: Arc::new(Mutex::new(
+            custom_css
+                .clone()
+                .or_else(|| config.custom_css_path())
+                .and_then(|path| read_custom_css(&path)),
+        )),
+        css_override: custom_css,
+        plantuml_cache: Arc::new(Mutex::new(HashMap::new())),
+        plantuml_backend: Arc::new(Mutex::new(None)),
+        expected_write: Arc::new(Mutex::new(None)),
+        ephemeral,
+    };
+    let app_state_for_setup = app_state.clone();
+    let close_behavior = config.close_behavior();
+    let window_save_pending = Arc::new(Mutex::new(false));
 
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
-        .manage(AppState {
-            content: content.clone(),
-            file_path: file_path_state.clone(),
-            file_name: file_name_state.clone(),
-            watcher_control: watcher_control.clone(),
-            is_large_file: is_large_file_state.clone(),
-            no_truncate: no_truncate_state.clone(),
-        })
+        .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             get_markdown_content,
-            open_dropped_file
+            open_dropped_file,
+            copy_file_path,
+            set_reduce_motion,
+            list_images,
+            get_raw_content,
+            export_plaintext,
+            render_html,
+            confirm_close,
+            file_meta,
+            set_show_toc,
+            get_section_ids,
+            check_anchors,
+            follow_link,
+            navigate_back,
+            set_content_width,
+            breadcrumb_for_line,
+            get_paragraphs,
+            content_changed_since,
+            reset_state,
+            frontend_ready,
+            export_snippet,
+            list_figures,
+            socket_info,
+            list_shortcuts,
+            get_block_structure,
+            reload_file,
+            get_outline,
+            get_recent_files,
+            set_no_truncate,
+            list_tabs,
+            switch_tab,
+            close_tab,
+            close_file,
+            save_scroll_position,
+            get_scroll_position,
+            list_directory_files,
+            search_content,
+            export_html,
+            export_pdf,
+            copy_section_html,
+            copy_document_html,
+            render_plantuml,
+            highlight_code,
+            list_code_themes,
+            set_theme,
+            update_config,
+            document_stats,
+            load_image,
+            toggle_task
         ])
         .setup(move |app| {
             // Start the socket server for daemon mode
-            let app_handle = app.handle().clone();
-            let socket_app_state = AppState {
-                content: content_for_socket.clone(),
-                file_path: file_path_for_socket.clone(),
-                file_name: file_name_for_socket.clone(),
-                watcher_control: watcher_control_for_socket.clone(),
-                is_large_file: is_large_file_for_socket.clone(),
-                no_truncate: no_truncate_for_socket.clone(),
-            };
-            start_socket_server(Arc::new(socket_app_state), app_handle);
+            if daemon {
+                let app_handle = app.handle().clone();
+                start_socket_server(
+                    Arc::new(app_state_for_setup.clone()),
+                    app_handle,
+                    remote_listen,
+                );
+            }
+
+            #[cfg(unix)]
+            start_signal_handler(app_state_for_setup.clone(), app.handle().clone());
+
+            // Closing the daemon's window only hides it, so give it a way back
+            if *app_state_for_setup
+                .serves_daemon
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+            {
+                if let Err(e) = create_tray(app.handle()) {
+                    eprintln!("Failed to create tray icon: {}", e);
+                }
+            }
+
+            // Self-terminate after a configured period of hidden inactivity
+            if let Some(minutes) = config.daemon_idle_timeout_minutes {
+                start_idle_timer(
+                    Duration::from_secs(minutes * 60),
+                    app_state_for_setup.clone(),
+                    app.handle().clone(),
+                );
+            }
+
             // Update window title and restore saved position/size
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.set_title(&window_title);
+                if let Some(theme) = config.theme.window_theme() {
+                    let _ = window.set_theme(Some(theme));
+                }
+                let _ = window.set_min_size(Some(tauri::PhysicalSize::new(
+                    MIN_WINDOW_WIDTH,
+                    MIN_WINDOW_HEIGHT,
+                )));
 
-                // Restore saved window position and size
+                // Restore saved window position and size, kept on a connected monitor
+                let saved_state = saved_state.fit_to_monitors(&monitor_work_areas(&window));
                 let _ =
                     window.set_position(tauri::PhysicalPosition::new(saved_state.x, saved_state.y));
                 let _ = window.set_size(tauri::PhysicalSize::new(
                     saved_state.width,
                     saved_state.height,
                 ));
+                // Applied after the normal geometry so un-maximizing returns to it
+                if saved_state.fullscreen {
+                    let _ = window.set_fullscreen(true);
+                } else if saved_state.maximized {
+                    let _ = window.maximize();
+                }
+            }
+
+            // The window starts hidden (tauri.conf.json). Show it now, or wait for
+            // frontend_ready up to defer_show_ms to avoid a blank-window flash.
+            match config.defer_show_ms {
+                Some(ms) => {
+                    let state = app_state_for_setup.clone();
+                    let app_handle = app.handle().clone();
+                    thread::spawn(move || {
+                        thread::sleep(Duration::from_millis(ms));
+                        reveal_window_once(&state, &app_handle);
+                    });
+                }
+                None => reveal_window_once(&app_state_for_setup, app.handle()),
+            }
+
+            // Hot-reload settings when the config file is edited
+            start_config_watcher(app_state_for_setup.clone(), app.handle().clone());
+
+            // Keep the directory-mode sidebar in sync with files added and removed
+            if let Some(dir) = directory.clone() {
+                start_directory_watcher(dir, app.handle().clone());
+            }
+
+            // Re-run the --cmd source on its interval
+            if let Some(source) = command_source.clone() {
+                start_command_refresh(source, app_state_for_setup.clone(), app.handle().clone());
             }
 
             // Set up file watcher with path switching support
             let app_handle = app.handle().clone();
-            let content_for_watcher = content.clone();
-            let file_path_for_watcher = file_path_state.clone();
+            let state_for_watcher = app_state_for_setup.clone();
+            let debounce = Duration::from_millis(
+                config
+                    .watch_debounce_ms
+                    .unwrap_or(DEFAULT_WATCH_DEBOUNCE_MS),
+            );
 
             // Channel for switching watched files
             let (path_tx, path_rx) = channel::<PathBuf>();
@@ -738,12 +5914,17 @@ fn run_app(
                     }
                 };
 
-                // Only start watching if we have an initial file
+                // Watch the file's directory rather than the file: editors that save by
+                // writing a temp file and renaming it over the target replace the inode,
+                // which would silently kill a watch on the file itself
                 let mut current_path = watch_path;
                 let mut watching = has_initial_file && current_path.exists();
+                let mut last_stale_check = Instant::now();
 
                 if watching {
-                    if let Err(e) = watcher.watch(&current_path, RecursiveMode::NonRecursive) {
+                    if let Err(e) =
+                        watcher.watch(watch_dir(&current_path), RecursiveMode::NonRecursive)
+                    {
                         eprintln!("Failed to watch file: {}", e);
                         watching = false;
                     }
@@ -752,13 +5933,18 @@ fn run_app(
                 loop {
                     // Check for new path to watch (non-blocking)
                     if let Ok(new_path) = path_rx.try_recv() {
-                        // Stop watching old file if we were watching
+                        // Stop watching old directory if we were watching
                         if watching {
-                            let _ = watcher.unwatch(&current_path);
+                            let _ = watcher.unwatch(watch_dir(&current_path));
                         }
 
-                        // Start watching new file
-                        if let Err(e) = watcher.watch(&new_path, RecursiveMode::NonRecursive) {
+                        // Start watching the new file's directory (an empty path means
+                        // the file was closed)
+                        if new_path.as_os_str().is_empty() {
+                            watching = false;
+                        } else if let Err(e) =
+                            watcher.watch(watch_dir(&new_path), RecursiveMode::NonRecursive)
+                        {
                             eprintln!("Failed to watch new file: {}", e);
                             watching = false;
                         } else {
@@ -770,34 +5956,50 @@ fn run_app(
 
                     // Check for file events (with timeout to allow path switching)
                     if let Ok(event) = event_rx.recv_timeout(Duration::from_millis(100)) {
-                        // Check for modify or write events
-                        if matches!(
-                            event.kind,
-                            notify::EventKind::Modify(_) | notify::EventKind::Create(_)
-                        ) {
-                            // Small delay to ensure file write is complete
-                            thread::sleep(Duration::from_millis(50));
-
-                            // Get current watched path from state
-                            let watched_path = file_path_for_watcher
-                                .lock()
-                                .unwrap_or_else(|e| e.into_inner())
-                                .clone();
-
-                            // Read updated content
-                            if let Ok(new_content) = fs::read_to_string(&watched_path) {
-                                if !new_content.trim().is_empty() {
-                                    // Update shared state
-                                    if let Ok(mut content) = content_for_watcher.lock() {
-                                        *content = new_content;
-                                    }
-
-                                    // Emit event to frontend
-                                    if let Some(window) = app_handle.get_webview_window("main") {
-                                        let _ = window.emit("file-changed", ());
-                                    }
+                        if !is_change_to(&event, &current_path) {
+                            continue;
+                        }
+
+                        // Wait until the file has been quiet for the debounce period,
+                        // so a burst of events from one save triggers a single reload
+                        let mut deadline = Instant::now() + debounce;
+                        while let Some(remaining) = deadline.checked_duration_since(Instant::now())
+                        {
+                            match event_rx.recv_timeout(remaining) {
+                                Ok(event) if is_change_to(&event, &current_path) => {
+                                    deadline = Instant::now() + debounce;
                                 }
+                                Ok(_) => {}
+                                Err(_) => break,
+                            }
+                        }
+
+                        // Editors that save by replacing the file remove it briefly, so only
+                        // a file still missing after the burst counts as deleted
+                        if current_path.exists() {
+                            reload_from_disk(&state_for_watcher, &app_handle);
+                        } else {
+                            handle_file_deleted(&state_for_watcher, &app_handle);
+                        }
+                    }
+
+                    // Watches can go stale across sleep/wake; if the mtime moved without an
+                    // event, re-issue the watch and re-read the file
+                    if watching && last_stale_check.elapsed() >= STALE_WATCH_CHECK_INTERVAL {
+                        last_stale_check = Instant::now();
+                        let last_seen = *state_for_watcher
+                            .last_seen_mtime
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner());
+                        let on_disk = file_mtime(&current_path);
+                        if on_disk.is_some() && on_disk != last_seen {
+                            let _ = watcher.unwatch(watch_dir(&current_path));
+                            if let Err(e) =
+                                watcher.watch(watch_dir(&current_path), RecursiveMode::NonRecursive)
+                            {
+                                eprintln!("Failed to re-watch file: {}", e);
                             }
+                            reload_from_disk(&state_for_watcher, &app_handle);
                         }
                     }
                 }
@@ -805,33 +6007,568 @@ fn run_app(
 
             Ok(())
         })
-        .on_window_event(|window, event| {
+        .on_window_event(move |window, event| {
             match event {
                 tauri::WindowEvent::CloseRequested { api, .. } => {
-                    // Save window state before closing
-                    if let Ok(position) = window.outer_position() {
-                        if let Ok(size) = window.outer_size() {
-                            let state = WindowState {
-                                x: position.x,
-                                y: position.y,
-                                width: size.width,
-                                height: size.height,
-                            };
-                            if let Err(e) = state.save() {
-                                eprintln!("Failed to save window state: {}", e);
+                    // Save window state before closing (no state writes in ephemeral mode)
+                    if !ephemeral {
+                        save_window_state(window);
+                    }
+                    // Only the daemon instance can be re-shown, so others never hide
+                    let serves_daemon = *window
+                        .state::<AppState>()
+                        .serves_daemon
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner());
+                    match close_behavior {
+                        CloseBehavior::Hide | CloseBehavior::Ask if !serves_daemon => {
+                            window.app_handle().exit(0);
+                        }
+                        CloseBehavior::Quit => {
+                            remove_socket_file(&window.state::<AppState>());
+                            window.app_handle().exit(0);
+                        }
+                        CloseBehavior::Ask => {
+                            // The frontend answers via confirm_close
+                            if let Err(e) = window.emit("confirm-close", ()) {
+                                eprintln!("Failed to emit confirm-close event: {}", e);
+                            }
+                            api.prevent_close();
+                        }
+                        CloseBehavior::Hide => {
+                            // Hide window instead of closing (daemon mode)
+                            window.state::<AppState>().mark_active();
+                            if let Err(e) = window.hide() {
+                                eprintln!("Failed to hide window: {}", e);
                             }
+                            // Prevent the default close behavior
+                            api.prevent_close();
+                        }
+                    }
+                }
+                // Following the OS: pass its color scheme changes on to the frontend
+                tauri::WindowEvent::ThemeChanged(os_theme) => {
+                    let theme = window
+                        .state::<AppState>()
+                        .config
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .theme;
+                    if theme.is_system() {
+                        if let Err(e) =
+                            window.emit("theme-changed", ThemeChanged::new(theme, *os_theme))
+                        {
+                            eprintln!("Failed to emit theme-changed event: {}", e);
                         }
                     }
-                    // Hide window instead of closing (daemon mode)
-                    if let Err(e) = window.hide() {
-                        eprintln!("Failed to hide window: {}", e);
+                }
+                // Also save as the window moves so a crash keeps the last geometry
+                tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_) => {
+                    if !ephemeral {
+                        schedule_window_state_save(window, &window_save_pending);
                     }
-                    // Prevent the default close behavior
-                    api.prevent_close();
                 }
                 _ => {}
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app_handle, event| {
+            // Every way out (Quit, idle timeout, signals) ends here; don't leave the
+            // socket for the next launch to trip over
+            if let tauri::RunEvent::Exit = event {
+                remove_socket_file(&app_handle.state::<AppState>());
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh, empty directory under the system temp dir for one test
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("glance-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A document directory `docs/` with `docs/img/a.png`, next to `secret.png`
+    fn image_tree(name: &str) -> (PathBuf, PathBuf) {
+        let root = scratch_dir(name);
+        let docs = root.join("docs");
+        fs::create_dir_all(docs.join("img")).unwrap();
+        fs::write(docs.join("img/a.png"), b"png").unwrap();
+        fs::write(root.join("secret.png"), b"secret").unwrap();
+        (root, docs)
+    }
+
+    #[test]
+    fn toggled_task_content_flips_only_the_box() {
+        let content = "# Todo\n\n  - [ ] write\r\n  1) [x] ship\n";
+        assert_eq!(
+            toggled_task_content(content, content, 2, true)
+                .unwrap()
+                .as_deref(),
+            Some("# Todo\n\n  - [x] write\r\n  1) [x] ship\n")
+        );
+        assert_eq!(
+            toggled_task_content(content, content, 3, false)
+                .unwrap()
+                .as_deref(),
+            Some("# Todo\n\n  - [ ] write\r\n  1) [ ] ship\n")
+        );
+        // Already in the requested state: nothing to write
+        assert_eq!(toggled_task_content(content, content, 3, true), Ok(None));
+        assert!(toggled_task_content(content, content, 0, true).is_err());
+    }
+
+    #[test]
+    fn toggled_task_content_keeps_external_edits() {
+        let shown = "- [ ] one\n- [ ] two\n";
+        let on_disk = "- [ ] one\n- [ ] two\n- [ ] three\n";
+        assert_eq!(
+            toggled_task_content(on_disk, shown, 0, true)
+                .unwrap()
+                .as_deref(),
+            Some("- [x] one\n- [ ] two\n- [ ] three\n")
+        );
+    }
+
+    #[test]
+    fn toggled_task_content_refuses_a_changed_line() {
+        let shown = "- [ ] one\n- [ ] two\n";
+        let on_disk = "- [ ] zero\n- [ ] one\n- [ ] two\n";
+        assert!(toggled_task_content(on_disk, shown, 0, true).is_err());
+        assert!(toggled_task_content("- [x] one\n", "- [ ] one\n", 0, true).is_err());
+    }
+
+    #[test]
+    fn resolve_within_accepts_paths_inside_base() {
+        let (_root, docs) = image_tree("within-inside");
+        let expected = fs::canonicalize(docs.join("img/a.png")).unwrap();
+        assert_eq!(resolve_within(&docs, "img/a.png"), Some(expected.clone()));
+        assert_eq!(
+            resolve_within(&docs, "./img/../img/a.png?raw=1#top"),
+            Some(expected)
+        );
+        assert_eq!(resolve_within(&docs, "img/missing.png"), None);
+    }
+
+    #[test]
+    fn resolve_within_rejects_parent_traversal() {
+        let (_root, docs) = image_tree("within-dotdot");
+        assert_eq!(resolve_within(&docs, "../secret.png"), None);
+        assert_eq!(resolve_within(&docs, "img/../../secret.png"), None);
+        assert_eq!(resolve_within(&docs, "../../../../../../etc/passwd"), None);
+    }
+
+    #[test]
+    fn resolve_within_rejects_absolute_paths_outside_base() {
+        let (root, docs) = image_tree("within-absolute");
+        let secret = root.join("secret.png");
+        assert_eq!(resolve_within(&docs, &secret.to_string_lossy()), None);
+        assert_eq!(resolve_within(&docs, "/etc/passwd"), None);
+    }
+
+    #[test]
+    fn image_ref_only_describes_images_inside_the_document_directory() {
+        let (root, docs) = image_tree("image-ref");
+        let image = |target: &str| {
+            image_ref(
+                &docs,
+                markdown::LinkRef {
+                    text: String::new(),
+                    target: target.to_string(),
+                    line: 0,
+                    is_image: true,
+                },
+            )
+        };
+
+        let inside = image("img/a.png");
+        assert!(inside.exists && !inside.outside_document);
+        assert_eq!(inside.size_bytes, Some(3));
+
+        let missing = image("img/missing.png");
+        assert!(!missing.exists && !missing.outside_document);
+
+        let secret = root.join("secret.png");
+        for outside in [image("../secret.png"), image(&secret.to_string_lossy())] {
+            assert!(outside.outside_document);
+            assert!(!outside.exists);
+            assert_eq!(outside.resolved_path, None);
+            assert_eq!(outside.size_bytes, None);
+        }
+
+        assert!(!image("https://example.com/a.png").outside_document);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_within_follows_symlinks() {
+        let (root, docs) = image_tree("within-symlink");
+        std::os::unix::fs::symlink(root.join("secret.png"), docs.join("out.png")).unwrap();
+        std::os::unix::fs::symlink(root.as_path(), docs.join("up")).unwrap();
+        std::os::unix::fs::symlink(docs.join("img/a.png"), docs.join("in.png")).unwrap();
+
+        assert_eq!(resolve_within(&docs, "out.png"), None);
+        assert_eq!(resolve_within(&docs, "up/secret.png"), None);
+        assert_eq!(
+            resolve_within(&docs, "in.png"),
+            Some(fs::canonicalize(docs.join("img/a.png")).unwrap())
+        );
+    }
+
+    #[test]
+    fn standalone_html_embeds_only_images_inside_the_document_tree() {
+        let (root, docs) = image_tree("export-images");
+        let doc = docs.join("doc.md");
+        let content = format!(
+            "![in](img/a.png)\n\n![up](../secret.png)\n\n![abs]({})\n",
+            root.join("secret.png").display()
+        );
+        let html = standalone_html(
+            &content,
+            &doc.to_string_lossy(),
+            "doc.md",
+            &AppConfig::default(),
+        );
+        assert_eq!(html.matches("data:image/png;base64,").count(), 1);
+        assert!(html.contains(&render::base64_encode(b"png")));
+        assert!(!html.contains(&render::base64_encode(b"secret")));
+    }
+
+    #[test]
+    fn standalone_html_respects_max_image_size() {
+        let (_root, docs) = image_tree("export-size");
+        let doc = docs.join("doc.md");
+        let config = AppConfig {
+            max_image_size: Some(ByteSize::Bytes(2)),
+            ..AppConfig::default()
+        };
+        let html = standalone_html(
+            "![in](img/a.png)\n",
+            &doc.to_string_lossy(),
+            "doc.md",
+            &config,
+        );
+        assert!(!html.contains("data:image/png;base64,"));
+    }
+
+    fn section_changes(old: &str, new: &str) -> Option<(Vec<usize>, Vec<usize>, Vec<usize>)> {
+        diff_sections(&extract_sections(old), &extract_sections(new))
+            .map(|changes| (changes.changed, changes.added, changes.removed))
+    }
+
+    fn numbered_doc(sections: usize) -> String {
+        (1..=sections)
+            .map(|n| format!("# S{}\n\nbody {}\n\n", n, n))
+            .collect()
+    }
+
+    #[test]
+    fn diff_sections_reports_an_edited_section() {
+        let old = numbered_doc(6);
+        let new = old.replace("body 3", "edited");
+        assert_eq!(section_changes(&old, &new), Some((vec![2], vec![], vec![])));
+    }
+
+    #[test]
+    fn diff_sections_reports_added_and_removed_sections() {
+        let old = numbered_doc(6);
+        let added = old.replace("# S4", "# New\n\nnew\n\n# S4");
+        assert_eq!(
+            section_changes(&old, &added),
+            Some((vec![], vec![3], vec![]))
+        );
+
+        let removed = old.replace("# S4\n\nbody 4\n\n", "");
+        assert_eq!(
+            section_changes(&old, &removed),
+            Some((vec![], vec![], vec![3]))
+        );
+    }
+
+    #[test]
+    fn diff_sections_falls_back_on_large_or_no_changes() {
+        let old = numbered_doc(4);
+        assert_eq!(section_changes(&old, &old), None);
+        let rewritten = old.replace("body", "text");
+        assert_eq!(section_changes(&old, &rewritten), None);
+    }
+
+    #[test]
+    fn frame_round_trips_path_and_content() {
+        let content = "# Title\n\nline two\n\u{e9}\n";
+        let frame = encode_frame("/docs/notes.md", content);
+        assert!(frame.starts_with(FRAME_MAGIC.as_bytes()));
+
+        // However much of the frame the caller had already read
+        for split in [FRAME_MAGIC.len(), 20, frame.len()] {
+            let (received, mut rest) = frame.split_at(split);
+            assert_eq!(
+                read_frame(received.to_vec(), &mut rest, MAX_FRAMED_CONTENT),
+                Ok(("/docs/notes.md".to_string(), content.to_string()))
+            );
+        }
+        assert_eq!(
+            read_frame(encode_frame("a.md", ""), &mut std::io::empty(), 0),
+            Ok(("a.md".to_string(), String::new()))
+        );
+    }
+
+    #[test]
+    fn read_frame_rejects_malformed_frames() {
+        let frame = encode_frame("/docs/notes.md", "content");
+        let read = |frame: &[u8]| read_frame(frame.to_vec(), &mut std::io::empty(), 64);
+        assert!(read(&frame[..frame.len() - 2]).is_err());
+        assert!(read(&frame[..20]).is_err());
+
+        let malformed = [
+            format!("{}abc\n/a.md\nx", FRAME_MAGIC),
+            format!("{}1\n/a.md\nxyz", FRAME_MAGIC),
+            format!("{}65\n/a.md\n", FRAME_MAGIC),
+            format!("{}{}", FRAME_MAGIC, "9".repeat(MAX_FRAME_HEADER + 1)),
+        ];
+        for frame in malformed {
+            assert!(read(frame.as_bytes()).is_err());
+        }
+    }
+
+    #[test]
+    fn read_frame_reports_content_shorter_than_declared() {
+        let header = format!("{}{}\n/a.md\n", FRAME_MAGIC, MAX_FRAMED_CONTENT);
+        let mut rest: &[u8] = b"only this";
+        assert_eq!(
+            read_frame(header.into_bytes(), &mut rest, MAX_FRAMED_CONTENT),
+            Err("Truncated frame content".to_string())
+        );
+    }
+
+    #[test]
+    fn remote_client_slots_are_capped_and_released() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let slots: Vec<_> = (0..MAX_REMOTE_CLIENTS)
+            .map(|_| RemoteClientSlot::acquire(&active).expect("slot"))
+            .collect();
+        assert!(RemoteClientSlot::acquire(&active).is_none());
+        drop(slots);
+        assert_eq!(active.load(Ordering::SeqCst), 0);
+        assert!(RemoteClientSlot::acquire(&active).is_some());
+    }
+
+    fn headings(content: &str) -> Vec<(u8, String, usize)> {
+        extract_sections(content)
+            .into_iter()
+            .map(|s| (s.level, s.title, s.start_line))
+            .collect()
+    }
+
+    #[test]
+    fn parse_setext_underline_levels() {
+        assert_eq!(parse_setext_underline("==="), Some(1));
+        assert_eq!(parse_setext_underline("="), Some(1));
+        assert_eq!(parse_setext_underline("   ---  "), Some(2));
+        assert_eq!(parse_setext_underline("    ---"), None);
+        assert_eq!(parse_setext_underline("-=-"), None);
+        assert_eq!(parse_setext_underline("- - -"), None);
+        assert_eq!(parse_setext_underline(""), None);
+    }
+
+    #[test]
+    fn can_be_setext_text_rejects_block_syntax() {
+        assert!(can_be_setext_text("Title"));
+        assert!(can_be_setext_text("   Title *with* emphasis"));
+        assert!(!can_be_setext_text(""));
+        assert!(!can_be_setext_text("    indented code"));
+        assert!(!can_be_setext_text("***"));
+        assert!(!can_be_setext_text("---"));
+        assert!(!can_be_setext_text("- item"));
+        assert!(!can_be_setext_text("1. item"));
+        assert!(!can_be_setext_text("> quote"));
+        assert!(!can_be_setext_text("```"));
+    }
+
+    #[test]
+    fn extract_sections_mixes_atx_and_setext_headings() {
+        let content = "Intro text\n\nTitle\n=====\n\n## Atx\n\nSub\n---\nbody\n";
+        assert_eq!(
+            headings(content),
+            vec![
+                (0, "Introduction".to_string(), 0),
+                (1, "Title".to_string(), 2),
+                (2, "Atx".to_string(), 5),
+                (2, "Sub".to_string(), 7),
+            ]
+        );
+        let sections = extract_sections(content);
+        assert_eq!(sections[1].content, "Title\n=====\n");
+        assert_eq!(sections[3].content, "Sub\n---\nbody");
+        assert_eq!(sections[3].anchor, "sub");
+    }
+
+    #[test]
+    fn extract_sections_keeps_rules_and_code_out_of_headings() {
+        let content = "# Top\n\n---\n\n- item\n---\n```\nCode\n===\n```\n";
+        assert_eq!(headings(content), vec![(1, "Top".to_string(), 0)]);
+    }
+
+    #[test]
+    fn self_write_matches_only_its_own_recent_content() {
+        let written = "- [x] done\n";
+        let at = Instant::now();
+        let write = SelfWrite {
+            hash: content_hash(written),
+            at,
+        };
+
+        // Reading back glance's own write is skipped
+        assert!(write.matches(written, at));
+        assert!(write.matches(written, at + Duration::from_millis(100)));
+        // An external edit right after still reloads
+        assert!(!write.matches("- [x] done\nmore\n", at + Duration::from_millis(100)));
+        // So does the same content once the window has passed
+        assert!(!write.matches(written, at + SELF_WRITE_WINDOW));
+    }
+
+    #[test]
+    fn content_hash_fits_a_javascript_number() {
+        assert_eq!(content_hash("a"), content_hash("a"));
+        assert_ne!(content_hash("a"), content_hash("b"));
+        assert!(content_hash("anything") < 1 << 53);
+    }
+
+    fn event(kind: notify::EventKind, paths: &[&Path]) -> Event {
+        paths.iter().fold(Event::new(kind), |event, path| {
+            event.add_path(path.to_path_buf())
+        })
+    }
+
+    #[test]
+    fn is_change_to_matches_a_rename_over_save() {
+        use notify::event::{
+            AccessKind, CreateKind, DataChange, ModifyKind, RemoveKind, RenameMode,
+        };
+        use notify::EventKind;
+
+        let dir = Path::new("/docs");
+        let target = dir.join("notes.md");
+        let temp = dir.join(".notes.md.swp");
+        let rename = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+
+        // Writing the temp file doesn't touch the target
+        assert!(!is_change_to(
+            &event(EventKind::Create(CreateKind::File), &[&temp]),
+            &target
+        ));
+        assert!(!is_change_to(
+            &event(
+                EventKind::Modify(ModifyKind::Data(DataChange::Any)),
+                &[&temp]
+            ),
+            &target
+        ));
+        // Renaming it over the target does, in either event shape
+        assert!(is_change_to(&event(rename, &[&temp, &target]), &target));
+        assert!(is_change_to(
+            &event(
+                EventKind::Modify(ModifyKind::Name(RenameMode::To)),
+                &[&target]
+            ),
+            &target
+        ));
+        assert!(is_change_to(
+            &event(EventKind::Create(CreateKind::File), &[&target]),
+            &target
+        ));
+        assert!(is_change_to(
+            &event(EventKind::Remove(RemoveKind::File), &[&target]),
+            &target
+        ));
+        // Reads and other files in the directory don't count
+        assert!(!is_change_to(
+            &event(EventKind::Access(AccessKind::Any), &[&target]),
+            &target
+        ));
+        assert!(!is_change_to(
+            &event(rename, &[&dir.join("other.md")]),
+            &target
+        ));
+    }
+
+    #[test]
+    fn watch_dir_is_the_parent() {
+        assert_eq!(watch_dir(Path::new("/docs/notes.md")), Path::new("/docs"));
+    }
+
+    #[test]
+    fn directory_watch_survives_rename_over_saves() {
+        let dir = scratch_dir("watch-rename");
+        let target = dir.join("notes.md");
+        fs::write(&target, "one").unwrap();
+
+        let (event_tx, event_rx) = channel();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: Result<Event, notify::Error>| {
+                if let Ok(event) = res {
+                    let _ = event_tx.send(event);
+                }
+            },
+            Config::default(),
+        )
+        .unwrap();
+        watcher
+            .watch(watch_dir(&target), RecursiveMode::NonRecursive)
+            .unwrap();
+
+        // A second save only reaches us if the first didn't kill the watch
+        for (save, text) in ["two", "three"].into_iter().enumerate() {
+            let temp = dir.join(format!(".notes.md.{}.tmp", save));
+            fs::write(&temp, text).unwrap();
+            fs::rename(&temp, &target).unwrap();
+
+            let deadline = Instant::now() + Duration::from_secs(5);
+            let seen = loop {
+                let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                    break false;
+                };
+                match event_rx.recv_timeout(remaining) {
+                    Ok(event) if is_change_to(&event, &target) => break true,
+                    Ok(_) => {}
+                    Err(_) => break false,
+                }
+            };
+            assert!(seen, "no change event for save {}", save + 1);
+            assert_eq!(fs::read_to_string(&target).unwrap(), text);
+
+            // Let the rest of this save's events arrive before the next one
+            thread::sleep(Duration::from_millis(200));
+            while event_rx.try_recv().is_ok() {}
+        }
+    }
+
+    #[test]
+    fn ui_settings_refuse_paths_programs_and_addresses() {
+        let patch = |value: serde_json::Value| value.as_object().unwrap().clone();
+        let allowed = patch(serde_json::json!({
+            "theme": "dark",
+            "number_headings": null,
+            "extensions": { "mermaid": true, "plantuml": false }
+        }));
+        assert_eq!(check_ui_settings(&allowed), Ok(()));
+
+        for refused in [
+            serde_json::json!({ "custom_css": "/tmp/evil.css" }),
+            serde_json::json!({ "remote_listen": "0.0.0.0:47292" }),
+            serde_json::json!({ "extra_extensions": ["sh"] }),
+            serde_json::json!({ "extensions": { "plantuml_jar": "/tmp/evil.jar" } }),
+            serde_json::json!({ "extensions": { "plantuml_server": "http://evil" } }),
+            serde_json::json!({ "extensions": null }),
+            serde_json::json!({ "theme": "dark", "unknown": 1 }),
+        ] {
+            assert!(check_ui_settings(&patch(refused)).is_err());
+        }
+    }
 }
@@ -0,0 +1,931 @@
+//! Lightweight line-based scans over markdown source
+
+/// Whether a line opens or closes a fenced code block
+pub fn is_fence_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// An inline link (`[text](target)`) or image (`![alt](src)`) found in the document
+#[derive(Clone, serde::Serialize)]
+pub struct LinkRef {
+    /// Link text or image alt text
+    pub text: String,
+    /// Raw link target or image source, without any title
+    pub target: String,
+    /// Line number of the reference (0-indexed)
+    pub line: usize,
+    /// Whether this is an image rather than a link
+    pub is_image: bool,
+}
+
+/// Extract inline links and images, ignoring code fences and inline code spans
+pub fn extract_links(content: &str) -> Vec<LinkRef> {
+    let mut links = Vec::new();
+    let mut in_code_block = false;
+
+    for (line_num, line) in content.lines().enumerate() {
+        if is_fence_line(line) {
+            in_code_block = !in_code_block;
+            continue;
+        }
+
+        if in_code_block {
+            continue;
+        }
+
+        scan_line_links(line, line_num, &mut links);
+    }
+
+    links
+}
+
+fn scan_line_links(line: &str, line_num: usize, links: &mut Vec<LinkRef>) {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => {
+                // Skip over an inline code span (matching run of backticks)
+                let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+                let fence = &line[i..i + run];
+                i += run;
+                if let Some(end) = line[i..].find(fence) {
+                    i += end + run;
+                }
+            }
+            b'[' => {
+                let is_image = i > 0 && bytes[i - 1] == b'!';
+                match parse_link_at(line, i) {
+                    Some((text, target, end)) => {
+                        links.push(LinkRef {
+                            text,
+                            target,
+                            line: line_num,
+                            is_image,
+                        });
+                        i = end;
+                    }
+                    None => i += 1,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+/// Parse `[text](target "title")` starting at the `[` at `start`.
+/// Returns (text, target, index just past the closing paren).
+pub fn parse_link_at(line: &str, start: usize) -> Option<(String, String, usize)> {
+    let bytes = line.as_bytes();
+    let text_end = find_closing(bytes, start, b'[', b']')?;
+    if bytes.get(text_end + 1) != Some(&b'(') {
+        return None;
+    }
+    let target_end = find_closing(bytes, text_end + 1, b'(', b')')?;
+
+    let text = line[start + 1..text_end].to_string();
+    let inner = line[text_end + 2..target_end].trim();
+    let target = if let Some(rest) = inner.strip_prefix('<') {
+        rest.split('>').next().unwrap_or_default()
+    } else {
+        inner.split_whitespace().next().unwrap_or_default()
+    };
+
+    Some((text, target.to_string(), target_end + 1))
+}
+
+/// Find the index of the bracket closing the one at `open_idx`, honoring nesting and escapes
+fn find_closing(bytes: &[u8], open_idx: usize, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open_idx;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'\\' {
+            i += 2;
+            continue;
+        }
+        if b == open {
+            depth += 1;
+        } else if b == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Inclusive range of lines (0-indexed)
+#[derive(Clone, Copy, serde::Serialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Find the line ranges of top-level `<details>`...`</details>` blocks, honoring nesting
+pub fn find_details_blocks(content: &str) -> Vec<LineRange> {
+    let mut blocks = Vec::new();
+    let mut in_code_block = false;
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (line_num, line) in content.lines().enumerate() {
+        if is_fence_line(line) {
+            in_code_block = !in_code_block;
+            continue;
+        }
+
+        if in_code_block {
+            continue;
+        }
+
+        let lower = line.to_lowercase();
+        let mut rest = lower.as_str();
+        loop {
+            let (tag_pos, is_open) = match (rest.find("<details"), rest.find("</details")) {
+                (Some(open), Some(close)) if open < close => (open, true),
+                (_, Some(close)) => (close, false),
+                (Some(open), None) => (open, true),
+                (None, None) => break,
+            };
+            let tag_len = if is_open {
+                "<details".len()
+            } else {
+                "</details".len()
+            };
+            let after = &rest[tag_pos + tag_len..];
+
+            // Require a tag boundary so e.g. `<detailsfoo>` doesn't match
+            if after.starts_with(['>', ' ', '\t']) || after.is_empty() {
+                if is_open {
+                    if depth == 0 {
+                        start = line_num;
+                    }
+                    depth += 1;
+                } else if depth > 0 {
+                    depth -= 1;
+                    if depth == 0 {
+                        blocks.push(LineRange {
+                            start,
+                            end: line_num,
+                        });
+                    }
+                }
+            }
+            rest = after;
+        }
+    }
+
+    blocks
+}
+
+/// Find GFM pipe tables with more than `max_columns` columns or any row longer than
+/// `max_width` characters. Tables are recognized by a header row followed by a
+/// delimiter row (`| --- | :-: |`).
+pub fn find_wide_tables(content: &str, max_columns: usize, max_width: usize) -> Vec<LineRange> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut tables = Vec::new();
+    let mut in_code_block = false;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if is_fence_line(line) {
+            in_code_block = !in_code_block;
+            i += 1;
+            continue;
+        }
+
+        let columns = lines
+            .get(i + 1)
+            .and_then(|next| delimiter_row_columns(next));
+        let Some(columns) = columns.filter(|_| !in_code_block && line.contains('|')) else {
+            i += 1;
+            continue;
+        };
+
+        let start = i;
+        let mut end = i + 1;
+        while lines
+            .get(end + 1)
+            .is_some_and(|row| row.contains('|') && !row.trim().is_empty())
+        {
+            end += 1;
+        }
+
+        let widest = lines[start..=end]
+            .iter()
+            .map(|row| row.trim().chars().count())
+            .max()
+            .unwrap_or(0);
+        if columns > max_columns || widest > max_width {
+            tables.push(LineRange { start, end });
+        }
+        i = end + 1;
+    }
+
+    tables
+}
+
+/// Number of columns if the line is a table delimiter row
+fn delimiter_row_columns(line: &str) -> Option<usize> {
+    let trimmed = line.trim();
+    if !trimmed.contains('-') {
+        return None;
+    }
+    let inner = trimmed.strip_prefix('|').unwrap_or(trimmed);
+    let inner = inner.strip_suffix('|').unwrap_or(inner);
+    let cells: Vec<&str> = inner.split('|').map(str::trim).collect();
+    let is_delimiter = cells.iter().all(|cell| {
+        let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+        !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+    });
+    (is_delimiter && (cells.len() > 1 || trimmed.starts_with('|'))).then_some(cells.len())
+}
+
+/// An ordered list whose first item is numbered something other than 1
+#[derive(Clone, Copy, serde::Serialize)]
+pub struct OrderedListStart {
+    /// Line of the list's first item (0-indexed)
+    pub line: usize,
+    /// Authored start number
+    pub start: u64,
+}
+
+/// Find ordered lists that start at a number other than 1 (`5. item`), so the
+/// frontend can set the list's `start` attribute. Nested lists are tracked by indent.
+pub fn find_ordered_list_starts(content: &str) -> Vec<OrderedListStart> {
+    let mut starts = Vec::new();
+    // Indents of the ordered lists currently open, outermost first
+    let mut open: Vec<usize> = Vec::new();
+    let mut in_code_block = false;
+    let mut prev_blank = true;
+
+    for (line_num, line) in content.lines().enumerate() {
+        let indent = line.len() - line.trim_start().len();
+
+        if is_fence_line(line) {
+            if !in_code_block && prev_blank {
+                open.retain(|i| *i < indent);
+            }
+            in_code_block = !in_code_block;
+            prev_blank = false;
+            continue;
+        }
+
+        if in_code_block {
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            prev_blank = true;
+            continue;
+        }
+
+        let body = line.trim_start();
+        if let Some(number) = ordered_item_number(body) {
+            open.retain(|i| *i <= indent);
+            if open.last() != Some(&indent) {
+                if number != 1 {
+                    starts.push(OrderedListStart {
+                        line: line_num,
+                        start: number,
+                    });
+                }
+                open.push(indent);
+            }
+        } else if body.starts_with(['-', '*', '+']) && body[1..].starts_with([' ', '\t']) {
+            open.retain(|i| *i < indent);
+        } else if body.starts_with('#') {
+            open.clear();
+        } else if prev_blank {
+            // A paragraph after a blank line ends the lists it isn't indented under
+            open.retain(|i| *i < indent);
+        }
+        prev_blank = false;
+    }
+
+    starts
+}
+
+/// The number of an ordered list item (`12. text` or `12) text`)
+pub fn ordered_item_number(body: &str) -> Option<u64> {
+    let digits = body.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+    let rest = &body[digits..];
+    let is_item = rest.starts_with(". ") || rest.starts_with(") ") || rest == "." || rest == ")";
+    if is_item {
+        body[..digits].parse().ok()
+    } else {
+        None
+    }
+}
+
+/// A GFM task list item (`- [ ] todo`, `1. [x] done`)
+#[derive(Clone, Copy, serde::Serialize)]
+pub struct TaskItem {
+    /// Line of the item (0-indexed)
+    pub line: usize,
+    pub checked: bool,
+}
+
+/// Find task list items outside code blocks, in document order (the order the
+/// rendered checkboxes appear in)
+pub fn find_task_items(content: &str) -> Vec<TaskItem> {
+    let mut items = Vec::new();
+    let mut in_code_block = false;
+
+    for (line_num, line) in content.lines().enumerate() {
+        if is_fence_line(line) {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        if let Some((_, checked)) = task_marker(line) {
+            items.push(TaskItem {
+                line: line_num,
+                checked,
+            });
+        }
+    }
+
+    items
+}
+
+/// Byte offset of the character between the brackets of a task item's `[ ]`/`[x]`,
+/// and whether it's checked. Items in block quotes (`> - [ ] todo`) count.
+pub fn task_marker(line: &str) -> Option<(usize, bool)> {
+    let mut rest = line.trim_start();
+    while let Some(quoted) = rest.strip_prefix('>') {
+        rest = quoted.trim_start();
+    }
+
+    let marker_len = if rest.starts_with(['-', '*', '+']) {
+        1
+    } else {
+        ordered_item_number(rest)?;
+        rest.find(['.', ')'])? + 1
+    };
+    let after_marker = &rest[marker_len..];
+    let body = after_marker.trim_start_matches([' ', '\t']);
+    if body.len() == after_marker.len() {
+        return None;
+    }
+
+    // marked only treats `[ ] `, `[x] ` and `[X] ` (with the space) as a checkbox
+    let checked = match body.get(..4)? {
+        "[ ] " => false,
+        "[x] " | "[X] " => true,
+        _ => return None,
+    };
+    Some((line.len() - body.len() + 1, checked))
+}
+
+/// `content` with the task list item on `line` (0-indexed) checked or unchecked. Only
+/// the character between the brackets changes, so indentation, list markers and line
+/// endings are kept. None if the line isn't a task item outside a code block.
+pub fn set_task_checked(content: &str, line: usize, checked: bool) -> Option<String> {
+    if !find_task_items(content)
+        .iter()
+        .any(|task| task.line == line)
+    {
+        return None;
+    }
+    let line_start: usize = content.split_inclusive('\n').take(line).map(str::len).sum();
+    let (offset, _) = task_marker(content[line_start..].lines().next()?)?;
+    let at = line_start + offset;
+
+    let mut updated = content.to_string();
+    updated.replace_range(at..at + 1, if checked { "x" } else { " " });
+    Some(updated)
+}
+
+/// Languages named on opening code fences (```rust), in order of first use
+pub fn fence_languages(content: &str) -> Vec<String> {
+    let mut languages: Vec<String> = Vec::new();
+    let mut in_code_block = false;
+
+    for line in content.lines() {
+        if !is_fence_line(line) {
+            continue;
+        }
+        if !in_code_block {
+            let language = fence_language(line);
+            if !language.is_empty() && !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+        in_code_block = !in_code_block;
+    }
+
+    languages
+}
+
+/// The language named on an opening fence, lowercased; empty if none. Attributes after
+/// the language token are ignored: ```Mermaid, ```mermaid {theme=dark}, ```mermaid,linenums
+/// and ```{.mermaid} all name `mermaid`.
+fn fence_language(line: &str) -> String {
+    let info = line.trim_start().trim_start_matches(['`', '~']).trim();
+    info.trim_start_matches(['{', '.'])
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '+' | '#'))
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// A fenced code block in one of the requested languages
+#[derive(Clone, serde::Serialize)]
+pub struct CodeFence {
+    /// Language named on the opening fence, lowercased
+    pub language: String,
+    /// Lines from the opening fence to the closing one
+    pub range: LineRange,
+    /// The code between the fences
+    pub source: String,
+}
+
+/// Find fenced code blocks whose language is one of `languages` (lowercase). An
+/// unclosed fence runs to the end of the document.
+pub fn find_code_fences(content: &str, languages: &[&str]) -> Vec<CodeFence> {
+    let mut fences = Vec::new();
+    // Language and first line of the open fence, while inside one
+    let mut open: Option<(String, usize)> = None;
+    let mut source: Vec<&str> = Vec::new();
+    let mut last_line = 0;
+
+    for (line_num, line) in content.lines().enumerate() {
+        last_line = line_num;
+        if !is_fence_line(line) {
+            if open.is_some() {
+                source.push(line);
+            }
+            continue;
+        }
+        match open.take() {
+            Some((language, start)) => {
+                if languages.contains(&language.as_str()) {
+                    fences.push(CodeFence {
+                        language,
+                        range: LineRange {
+                            start,
+                            end: line_num,
+                        },
+                        source: source.join("\n"),
+                    });
+                }
+                source.clear();
+            }
+            None => open = Some((fence_language(line), line_num)),
+        }
+    }
+
+    if let Some((language, start)) = open {
+        if languages.contains(&language.as_str()) {
+            fences.push(CodeFence {
+                language,
+                range: LineRange {
+                    start,
+                    end: last_line,
+                },
+                source: source.join("\n"),
+            });
+        }
+    }
+
+    fences
+}
+
+/// An Obsidian-style wikilink (`[[Target]]` or `[[Target|Alias]]`)
+#[derive(Clone)]
+pub struct WikiLink {
+    /// Linked page name, without any `#heading` part
+    pub target: String,
+    /// Display text after `|`, if given
+    pub alias: Option<String>,
+    /// Line number of the link (0-indexed)
+    pub line: usize,
+    /// Byte offsets of `[[` and just past `]]` within the line
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Find wikilinks outside code fences and inline code spans
+pub fn extract_wikilinks(content: &str) -> Vec<WikiLink> {
+    let mut links = Vec::new();
+    let mut in_code_block = false;
+
+    for (line_num, line) in content.lines().enumerate() {
+        if is_fence_line(line) {
+            in_code_block = !in_code_block;
+            continue;
+        }
+
+        if in_code_block {
+            continue;
+        }
+
+        let mut i = 0;
+        while i < line.len() {
+            let rest = &line[i..];
+            if rest.starts_with('`') {
+                // Skip over an inline code span (matching run of backticks)
+                let run = rest.chars().take_while(|c| *c == '`').count();
+                i += run + rest[run..].find(&rest[..run]).map_or(0, |end| end + run);
+                continue;
+            }
+
+            if let Some(inner) = rest.strip_prefix("[[") {
+                if let Some(close) = inner.find("]]") {
+                    let body = &inner[..close];
+                    let (target, alias) = match body.split_once('|') {
+                        Some((target, alias)) => (target, Some(alias.trim().to_string())),
+                        None => (body, None),
+                    };
+                    let target = target.split('#').next().unwrap_or_default().trim();
+                    if !target.is_empty() && !body.contains('[') {
+                        let end = i + 2 + close + 2;
+                        links.push(WikiLink {
+                            target: target.to_string(),
+                            alias,
+                            line: line_num,
+                            start: i,
+                            end,
+                        });
+                        i = end;
+                        continue;
+                    }
+                }
+            }
+
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    links
+}
+
+/// Hierarchical numbers (`2.3.1`) for headings given their levels in document order.
+/// Skipped levels don't add `.0` parts: an h3 directly under an h1 is numbered `1.1`,
+/// and a later h2 under the same h1 continues as `1.2`.
+pub fn number_headings(levels: &[u8]) -> Vec<String> {
+    // Open headings as (level, count among siblings)
+    let mut stack: Vec<(u8, usize)> = Vec::new();
+
+    levels
+        .iter()
+        .map(|&level| {
+            let mut sibling_count = 0;
+            while let Some(&(open_level, count)) = stack.last() {
+                if open_level <= level {
+                    break;
+                }
+                sibling_count = count;
+                stack.pop();
+            }
+
+            match stack.last_mut() {
+                Some((open_level, count)) if *open_level == level => *count += 1,
+                _ => stack.push((level, sibling_count + 1)),
+            }
+
+            stack
+                .iter()
+                .map(|(_, count)| count.to_string())
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .collect()
+}
+
+/// Kind of a top-level block element
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    Heading,
+    Paragraph,
+    Code,
+    List,
+    Blockquote,
+    Table,
+    ThematicBreak,
+}
+
+/// A top-level block and its inclusive line range (0-indexed)
+#[derive(Clone, Copy, serde::Serialize)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Map the document's top-level blocks with a single line-based pass. Inline markup
+/// is not parsed; nested blocks stay inside their list or blockquote.
+pub fn block_structure(content: &str) -> Vec<Block> {
+    let lines: Vec<&str> = content.lines().collect();
+    let is_blank = |i: usize| lines.get(i).is_none_or(|l| l.trim().is_empty());
+    let mut blocks = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim().is_empty() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let body = line.trim_start();
+        let indent = line.len() - body.len();
+
+        let kind = if is_fence_line(line) {
+            // Runs to the closing fence, or to the end of the document
+            let fence_char = body.as_bytes()[0] as char;
+            let fence = &body[..body.chars().take_while(|c| *c == fence_char).count()];
+            while i + 1 < lines.len() {
+                i += 1;
+                if lines[i].trim_start().starts_with(fence) {
+                    break;
+                }
+            }
+            BlockKind::Code
+        } else if indent >= 4 {
+            while i + 1 < lines.len() && (is_blank(i + 1) || is_indented_code(lines[i + 1])) {
+                i += 1;
+            }
+            while is_blank(i) {
+                i -= 1;
+            }
+            BlockKind::Code
+        } else if is_atx_heading(body) {
+            BlockKind::Heading
+        } else if is_thematic_break(body) {
+            BlockKind::ThematicBreak
+        } else if body.starts_with('>') {
+            while !is_blank(i + 1) {
+                i += 1;
+            }
+            BlockKind::Blockquote
+        } else if let Some(marker) = list_marker(body) {
+            // Continues while lines are indented or items with the same marker type;
+            // a different marker starts a new list
+            while let Some(j) = (i + 1..lines.len()).find(|j| !is_blank(*j)) {
+                let next = lines[j];
+                let continues = next.starts_with([' ', '\t'])
+                    || list_marker(next) == Some(marker)
+                    || (j == i + 1
+                        && list_marker(next).is_none()
+                        && !is_atx_heading(next)
+                        && !is_fence_line(next));
+                if !continues {
+                    break;
+                }
+                i = j;
+            }
+            BlockKind::List
+        } else if line.contains('|')
+            && lines
+                .get(i + 1)
+                .is_some_and(|l| delimiter_row_columns(l).is_some())
+        {
+            i += 1;
+            while lines
+                .get(i + 1)
+                .is_some_and(|l| l.contains('|') && !l.trim().is_empty())
+            {
+                i += 1;
+            }
+            BlockKind::Table
+        } else {
+            let mut kind = BlockKind::Paragraph;
+            while let Some(next) = lines.get(i + 1) {
+                let next_body = next.trim_start();
+                if next.trim().is_empty() {
+                    break;
+                }
+                if is_setext_underline(next_body) {
+                    i += 1;
+                    kind = BlockKind::Heading;
+                    break;
+                }
+                if is_fence_line(next)
+                    || is_atx_heading(next_body)
+                    || is_thematic_break(next_body)
+                    || next_body.starts_with('>')
+                    || list_marker(next_body).is_some()
+                {
+                    break;
+                }
+                i += 1;
+            }
+            kind
+        };
+
+        blocks.push(Block {
+            kind,
+            start,
+            end: i,
+        });
+        i += 1;
+    }
+
+    blocks
+}
+
+fn is_indented_code(line: &str) -> bool {
+    line.starts_with("    ") || line.starts_with('\t')
+}
+
+fn is_atx_heading(body: &str) -> bool {
+    let hashes = body.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&hashes)
+        && (body[hashes..].is_empty() || body[hashes..].starts_with([' ', '\t']))
+}
+
+fn is_thematic_break(body: &str) -> bool {
+    let compact: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|marker| compact.chars().all(|c| c == *marker))
+}
+
+fn is_setext_underline(body: &str) -> bool {
+    let trimmed = body.trim_end();
+    !trimmed.is_empty() && (trimmed.chars().all(|c| c == '=') || trimmed.chars().all(|c| c == '-'))
+}
+
+/// The marker type of a list item: the bullet character, or `.`/`)` for ordered items
+fn list_marker(body: &str) -> Option<char> {
+    let body = body.trim_start();
+    let first = body.chars().next()?;
+    if matches!(first, '-' | '*' | '+') && (body.len() == 1 || body[1..].starts_with([' ', '\t'])) {
+        return Some(first);
+    }
+    ordered_item_number(body)?;
+    body.chars().find(|c| !c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_marker_finds_the_box() {
+        assert_eq!(task_marker("- [ ] todo"), Some((3, false)));
+        assert_eq!(task_marker("  * [x] done"), Some((5, true)));
+        assert_eq!(task_marker("12. [X] done"), Some((5, true)));
+        assert_eq!(task_marker("> - [ ] quoted"), Some((5, false)));
+    }
+
+    #[test]
+    fn task_marker_rejects_non_tasks() {
+        assert_eq!(task_marker("- [ ]"), None);
+        assert_eq!(task_marker("-[ ] todo"), None);
+        assert_eq!(task_marker("- [y] todo"), None);
+        assert_eq!(task_marker("[ ] todo"), None);
+        assert_eq!(task_marker("- plain item"), None);
+    }
+
+    #[test]
+    fn find_task_items_skips_code_blocks() {
+        let content = "- [ ] a\n```\n- [ ] code\n```\n1) [x] b\r\n";
+        let items: Vec<_> = find_task_items(content)
+            .iter()
+            .map(|item| (item.line, item.checked))
+            .collect();
+        assert_eq!(items, vec![(0, false), (4, true)]);
+    }
+
+    #[test]
+    fn set_task_checked_changes_one_character() {
+        let content = "\t- [ ] tab\n    + [x] spaces\r\n";
+        assert_eq!(
+            set_task_checked(content, 0, true).as_deref(),
+            Some("\t- [x] tab\n    + [x] spaces\r\n")
+        );
+        assert_eq!(
+            set_task_checked(content, 1, false).as_deref(),
+            Some("\t- [ ] tab\n    + [ ] spaces\r\n")
+        );
+        assert_eq!(set_task_checked(content, 1, true).as_deref(), Some(content));
+    }
+
+    #[test]
+    fn set_task_checked_rejects_other_lines() {
+        let content = "text\n```\n- [ ] code\n```\n";
+        assert_eq!(set_task_checked(content, 0, true), None);
+        assert_eq!(set_task_checked(content, 2, true), None);
+        assert_eq!(set_task_checked(content, 10, true), None);
+    }
+
+    #[test]
+    fn numbers_nested_headings() {
+        assert_eq!(
+            number_headings(&[1, 2, 3, 3, 2, 1, 2]),
+            vec!["1", "1.1", "1.1.1", "1.1.2", "1.2", "2", "2.1"]
+        );
+    }
+
+    #[test]
+    fn skipped_levels_add_no_zero_parts() {
+        assert_eq!(
+            number_headings(&[1, 3, 2, 4, 1]),
+            vec!["1", "1.1", "1.2", "1.2.1", "2"]
+        );
+        assert_eq!(number_headings(&[2, 2, 3]), vec!["1", "2", "2.1"]);
+    }
+
+    #[test]
+    fn multiple_top_level_headings_restart_nested_counts() {
+        assert_eq!(
+            number_headings(&[1, 2, 2, 1, 2]),
+            vec!["1", "1.1", "1.2", "2", "2.1"]
+        );
+        assert!(number_headings(&[]).is_empty());
+    }
+
+    fn blocks(content: &str) -> Vec<(BlockKind, usize, usize)> {
+        block_structure(content)
+            .iter()
+            .map(|block| (block.kind, block.start, block.end))
+            .collect()
+    }
+
+    #[test]
+    fn maps_each_block_kind() {
+        let content = "# Title\n\
+            \n\
+            Some text\n\
+            over two lines.\n\
+            \n\
+            ```rust\n\
+            fn main() {}\n\
+            ```\n\
+            \n\
+            - one\n\
+            - two\n\
+            \n\
+            > quoted\n\
+            > more\n\
+            \n\
+            | a | b |\n\
+            | --- | --- |\n\
+            | 1 | 2 |\n\
+            \n\
+            ---\n";
+        assert_eq!(
+            blocks(content),
+            vec![
+                (BlockKind::Heading, 0, 0),
+                (BlockKind::Paragraph, 2, 3),
+                (BlockKind::Code, 5, 7),
+                (BlockKind::List, 9, 10),
+                (BlockKind::Blockquote, 12, 13),
+                (BlockKind::Table, 15, 17),
+                (BlockKind::ThematicBreak, 19, 19),
+            ]
+        );
+    }
+
+    #[test]
+    fn setext_headings_and_indented_code() {
+        let content = "Title\n=====\n\n    code\n\n    more\n\nText\n";
+        assert_eq!(
+            blocks(content),
+            vec![
+                (BlockKind::Heading, 0, 1),
+                (BlockKind::Code, 3, 5),
+                (BlockKind::Paragraph, 7, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_keep_nested_content_and_split_on_marker_change() {
+        let content = "- one\n  nested\n\n- two\n* other\n1. first\n";
+        assert_eq!(
+            blocks(content),
+            vec![
+                (BlockKind::List, 0, 3),
+                (BlockKind::List, 4, 4),
+                (BlockKind::List, 5, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_fence_runs_to_the_end() {
+        assert_eq!(
+            blocks("Text\n```\n# not a heading\n"),
+            vec![(BlockKind::Paragraph, 0, 0), (BlockKind::Code, 1, 2)]
+        );
+    }
+}
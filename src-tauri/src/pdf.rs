@@ -0,0 +1,144 @@
+//! PDF export: print a standalone HTML document to a file from a hidden webview
+
+use std::path::Path;
+#[cfg(target_os = "linux")]
+use std::time::Duration;
+
+/// How long to wait for the webview to lay out and print before giving up
+#[cfg(target_os = "linux")]
+const PDF_EXPORT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Label of the hidden window used for printing
+#[cfg(target_os = "linux")]
+const PDF_WINDOW_LABEL: &str = "pdf-export";
+
+/// Base URI the export is loaded under, telling its load apart from the initial blank page
+#[cfg(target_os = "linux")]
+const PDF_BASE_URI: &str = "glance-export://document/";
+
+/// Page layout for PDF export. Every field is optional; the defaults are A4 portrait
+/// with 15mm margins.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct PdfOptions {
+    /// "a4", "a3", "a5", "letter" or "legal"
+    pub page_size: Option<String>,
+    /// Margin on all four sides, in millimetres
+    pub margin_mm: Option<f64>,
+    pub landscape: bool,
+}
+
+impl PdfOptions {
+    /// The PWG paper name for `page_size`
+    pub fn paper_name(&self) -> Result<&'static str, String> {
+        let size = self.page_size.as_deref().unwrap_or("a4");
+        match size.to_lowercase().as_str() {
+            "a3" => Ok("iso_a3"),
+            "a4" => Ok("iso_a4"),
+            "a5" => Ok("iso_a5"),
+            "letter" => Ok("na_letter"),
+            "legal" => Ok("na_legal"),
+            _ => Err(format!("Unsupported page size: {}", size)),
+        }
+    }
+
+    pub fn margin(&self) -> Result<f64, String> {
+        match self.margin_mm {
+            Some(mm) if !(0.0..=100.0).contains(&mm) => {
+                Err(format!("Margin must be between 0 and 100mm, got {}", mm))
+            }
+            Some(mm) => Ok(mm),
+            None => Ok(15.0),
+        }
+    }
+}
+
+/// Print `document` (a complete HTML page) to a PDF at `out_path`. Blocks until the
+/// print finishes, so call it off the main thread.
+#[cfg(target_os = "linux")]
+pub fn print_to_pdf(
+    app: &tauri::AppHandle,
+    document: String,
+    out_path: &Path,
+    options: &PdfOptions,
+) -> Result<(), String> {
+    use std::sync::mpsc::channel;
+    use webkit2gtk::{LoadEvent, PrintOperationExt, WebViewExt};
+
+    let paper_name = options.paper_name()?;
+    let margin = options.margin()?;
+    let landscape = options.landscape;
+    let output_uri = gtk::glib::filename_to_uri(out_path, None)
+        .map_err(|e| format!("Invalid output path {}: {}", out_path.display(), e))?
+        .to_string();
+
+    // A previous export that timed out may have left its window behind
+    if let Some(window) = tauri::Manager::get_webview_window(app, PDF_WINDOW_LABEL) {
+        let _ = window.destroy();
+    }
+    let window = tauri::WebviewWindowBuilder::new(
+        app,
+        PDF_WINDOW_LABEL,
+        tauri::WebviewUrl::External("about:blank".parse().unwrap()),
+    )
+    .visible(false)
+    .build()
+    .map_err(|e| format!("Failed to create print window: {}", e))?;
+
+    let (tx, rx) = channel::<Result<(), String>>();
+    let setup = window.with_webview(move |webview| {
+        let webview = webview.inner();
+        webview.connect_load_changed(move |webview, event| {
+            if event != LoadEvent::Finished || webview.uri().as_deref() != Some(PDF_BASE_URI) {
+                return;
+            }
+            let settings = gtk::PrintSettings::new();
+            settings.set_printer("Print to File");
+            settings.set("output-file-format", Some("pdf"));
+            settings.set("output-uri", Some(&output_uri));
+
+            let page_setup = gtk::PageSetup::new();
+            page_setup.set_paper_size(&gtk::PaperSize::new(Some(paper_name)));
+            if landscape {
+                page_setup.set_orientation(gtk::PageOrientation::Landscape);
+            }
+            page_setup.set_top_margin(margin, gtk::Unit::Mm);
+            page_setup.set_bottom_margin(margin, gtk::Unit::Mm);
+            page_setup.set_left_margin(margin, gtk::Unit::Mm);
+            page_setup.set_right_margin(margin, gtk::Unit::Mm);
+
+            let operation = webkit2gtk::PrintOperation::new(webview);
+            operation.set_print_settings(&settings);
+            operation.set_page_setup(&page_setup);
+            let finished = tx.clone();
+            operation.connect_finished(move |_| {
+                let _ = finished.send(Ok(()));
+            });
+            let failed = tx.clone();
+            operation.connect_failed(move |_, e| {
+                let _ = failed.send(Err(format!("Failed to print PDF: {}", e)));
+            });
+            operation.print();
+        });
+        webview.load_html(&document, Some(PDF_BASE_URI));
+    });
+
+    let result = match setup {
+        Ok(()) => rx
+            .recv_timeout(PDF_EXPORT_TIMEOUT)
+            .unwrap_or_else(|_| Err("PDF export timed out".to_string())),
+        Err(e) => Err(format!("Failed to access print webview: {}", e)),
+    };
+    let _ = window.destroy();
+    result
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn print_to_pdf(
+    _app: &tauri::AppHandle,
+    _document: String,
+    _out_path: &Path,
+    _options: &PdfOptions,
+) -> Result<(), String> {
+    Err("PDF export is not supported on this platform".to_string())
+}
@@ -0,0 +1,447 @@
+//! Markdown to readable plain text conversion
+
+use crate::markdown::{is_fence_line, parse_link_at};
+
+/// Strip markdown syntax from a document, producing readable plain text
+pub fn to_plain_text(content: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut code_block: Option<Vec<&str>> = None;
+
+    for line in content.lines() {
+        if is_fence_line(line) {
+            match code_block.take() {
+                Some(block) => out.extend(dedent(&block)),
+                None => code_block = Some(Vec::new()),
+            }
+            continue;
+        }
+
+        if let Some(block) = code_block.as_mut() {
+            block.push(line);
+            continue;
+        }
+
+        if let Some(text) = plain_line(line) {
+            out.push(text);
+        }
+    }
+
+    // Unterminated fence: keep its content
+    if let Some(block) = code_block {
+        out.extend(dedent(&block));
+    }
+
+    let mut text = out.join("\n");
+    if content.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+/// Convert a single non-code line; returns None for lines that carry only syntax
+pub fn plain_line(line: &str) -> Option<String> {
+    let trimmed = line.trim();
+
+    // Setext underlines and thematic breaks
+    if is_rule_line(trimmed) {
+        return None;
+    }
+
+    // Table delimiter rows (| --- | :---: |)
+    if trimmed.starts_with('|') && trimmed.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ')) {
+        return None;
+    }
+
+    let indent_len = line.len() - line.trim_start().len();
+    let indent = line[..indent_len].replace('\t', "    ");
+    let mut body = line.trim_start();
+
+    // Blockquote markers
+    while let Some(rest) = body.strip_prefix('>') {
+        body = rest.trim_start();
+    }
+
+    // ATX headings
+    if let Some(title) = heading_text(body) {
+        return Some(title);
+    }
+
+    // Table rows become tab-separated cells
+    if body.starts_with('|') {
+        let cells: Vec<String> = body
+            .trim_matches('|')
+            .split('|')
+            .map(|cell| strip_inline(cell.trim()))
+            .collect();
+        return Some(cells.join("\t"));
+    }
+
+    // List markers, preserving nesting indentation
+    if let Some((marker, rest)) = split_list_marker(body) {
+        return Some(format!("{}{}{}", indent, marker, strip_inline(rest)));
+    }
+
+    Some(format!("{}{}", indent, strip_inline(body)))
+}
+
+/// Plain text of an ATX heading line (`## Title`), if it is one
+fn heading_text(body: &str) -> Option<String> {
+    let hashes = body.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&hashes) && (body[hashes..].starts_with(' ') || body[hashes..].is_empty()) {
+        let title = body[hashes..].trim().trim_end_matches('#').trim();
+        return Some(strip_inline(title));
+    }
+    None
+}
+
+/// Kind of block a paragraph came from
+#[derive(Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParagraphKind {
+    Heading,
+    Paragraph,
+    ListItem,
+}
+
+/// A speakable block of the document, stripped to plain text
+#[derive(Clone, serde::Serialize)]
+pub struct Paragraph {
+    pub text: String,
+    /// Line number where the block starts (0-indexed)
+    pub start_line: usize,
+    pub kind: ParagraphKind,
+}
+
+/// Split a document into headings, paragraphs, and list items as plain text.
+/// Code blocks and syntax-only lines (rules, table delimiters) are skipped.
+pub fn to_paragraphs(content: &str) -> Vec<Paragraph> {
+    let mut paragraphs = Vec::new();
+    let mut current: Option<Paragraph> = None;
+    let mut in_code_block = false;
+
+    fn flush(current: &mut Option<Paragraph>, paragraphs: &mut Vec<Paragraph>) {
+        if let Some(paragraph) = current.take() {
+            if !paragraph.text.trim().is_empty() {
+                paragraphs.push(paragraph);
+            }
+        }
+    }
+
+    for (line_num, line) in content.lines().enumerate() {
+        if is_fence_line(line) {
+            flush(&mut current, &mut paragraphs);
+            in_code_block = !in_code_block;
+            continue;
+        }
+
+        if in_code_block {
+            continue;
+        }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            flush(&mut current, &mut paragraphs);
+            continue;
+        }
+
+        // Setext underline turns the paragraph above it into a heading
+        if is_rule_line(trimmed) {
+            let is_setext = trimmed.starts_with(['=', '-'])
+                && current
+                    .as_ref()
+                    .is_some_and(|p| p.kind == ParagraphKind::Paragraph);
+            if is_setext {
+                if let Some(paragraph) = current.as_mut() {
+                    paragraph.kind = ParagraphKind::Heading;
+                }
+            }
+            flush(&mut current, &mut paragraphs);
+            continue;
+        }
+
+        let mut body = trimmed;
+        while let Some(rest) = body.strip_prefix('>') {
+            body = rest.trim_start();
+        }
+
+        if let Some(title) = heading_text(body) {
+            flush(&mut current, &mut paragraphs);
+            current = Some(Paragraph {
+                text: title,
+                start_line: line_num,
+                kind: ParagraphKind::Heading,
+            });
+            flush(&mut current, &mut paragraphs);
+            continue;
+        }
+
+        if let Some((_, rest)) = split_list_marker(body) {
+            flush(&mut current, &mut paragraphs);
+            current = Some(Paragraph {
+                text: strip_inline(rest),
+                start_line: line_num,
+                kind: ParagraphKind::ListItem,
+            });
+            continue;
+        }
+
+        let Some(text) = plain_line(line) else {
+            continue;
+        };
+        match current.as_mut() {
+            Some(paragraph) => {
+                paragraph.text.push(' ');
+                paragraph.text.push_str(text.trim());
+            }
+            None => {
+                current = Some(Paragraph {
+                    text: text.trim().to_string(),
+                    start_line: line_num,
+                    kind: ParagraphKind::Paragraph,
+                });
+            }
+        }
+    }
+
+    flush(&mut current, &mut paragraphs);
+    paragraphs
+}
+
+/// Whether a trimmed line is a thematic break or setext underline
+fn is_rule_line(trimmed: &str) -> bool {
+    let compact: String = trimmed.chars().filter(|c| *c != ' ').collect();
+    if compact.is_empty() {
+        return false;
+    }
+    if compact.chars().all(|c| c == '=') {
+        return true;
+    }
+    compact.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|marker| compact.chars().all(|c| c == *marker))
+}
+
+/// Split a list item into a normalized marker (`- ` or `N. `) and its text
+fn split_list_marker(body: &str) -> Option<(String, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(rest) = body.strip_prefix(bullet) {
+            return Some(("- ".to_string(), rest));
+        }
+    }
+
+    let digits = body.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && digits <= 9 {
+        let rest = &body[digits..];
+        if let Some(rest) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some((format!("{}. ", &body[..digits]), rest));
+        }
+    }
+
+    None
+}
+
+/// Strip inline markup: links, images, code spans, and emphasis
+pub fn strip_inline(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        match b {
+            b'\\' if i + 1 < bytes.len() && bytes[i + 1].is_ascii_punctuation() => {
+                out.push(bytes[i + 1] as char);
+                i += 2;
+            }
+            b'`' => {
+                let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+                let fence = &text[i..i + run];
+                match text[i + run..].find(fence) {
+                    Some(end) => {
+                        out.push_str(text[i + run..i + run + end].trim());
+                        i += run + end + run;
+                    }
+                    None => {
+                        out.push_str(fence);
+                        i += run;
+                    }
+                }
+            }
+            b'!' if bytes.get(i + 1) == Some(&b'[') => match parse_link_at(text, i + 1) {
+                Some((alt, _, end)) => {
+                    out.push_str(&format!("[image: {}]", strip_inline(&alt)));
+                    i = end;
+                }
+                None => {
+                    out.push('!');
+                    i += 1;
+                }
+            },
+            b'[' => match parse_link_at(text, i) {
+                Some((label, target, end)) => {
+                    let label = strip_inline(&label);
+                    if label == target || target.is_empty() {
+                        out.push_str(&label);
+                    } else {
+                        out.push_str(&format!("{} ({})", label, target));
+                    }
+                    i = end;
+                }
+                None => {
+                    out.push('[');
+                    i += 1;
+                }
+            },
+            b'<' => {
+                // Autolinks (<https://...>) keep their URL
+                match text[i..].find('>') {
+                    Some(end) if text[i + 1..i + end].contains("://") => {
+                        out.push_str(&text[i + 1..i + end]);
+                        i += end + 1;
+                    }
+                    _ => {
+                        out.push('<');
+                        i += 1;
+                    }
+                }
+            }
+            b'*' | b'_' | b'~' => {
+                let run = bytes[i..].iter().take_while(|c| **c == b).count();
+                let prev = text[..i].chars().next_back();
+                let next = text[i + run..].chars().next();
+                if is_emphasis_delimiter(b, run, prev, next) {
+                    i += run;
+                } else {
+                    out.push_str(&text[i..i + run]);
+                    i += run;
+                }
+            }
+            _ => {
+                let ch = text[i..].chars().next().unwrap_or_default();
+                out.push(ch);
+                i += ch.len_utf8();
+            }
+        }
+    }
+
+    out
+}
+
+/// Decide whether a run of `*`, `_`, or `~` is emphasis markup rather than literal text
+fn is_emphasis_delimiter(marker: u8, run: usize, prev: Option<char>, next: Option<char>) -> bool {
+    if marker == b'~' && run != 2 {
+        return false;
+    }
+
+    let prev_space = prev.is_none_or(char::is_whitespace);
+    let next_space = next.is_none_or(char::is_whitespace);
+
+    // Surrounded by whitespace on both sides (e.g. `2 * 3`) is literal
+    if prev_space && next_space {
+        return false;
+    }
+
+    // Intraword underscores (snake_case) are literal
+    if marker == b'_' {
+        let prev_word = prev.is_some_and(char::is_alphanumeric);
+        let next_word = next.is_some_and(char::is_alphanumeric);
+        if prev_word && next_word {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Remove the common leading indentation from a block of lines
+fn dedent(lines: &[&str]) -> Vec<String> {
+    let min_indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .iter()
+        .map(|l| l.get(min_indent..).unwrap_or("").to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_heading_markers() {
+        assert_eq!(
+            to_plain_text("# Title\n\n## Closed ##\nSub\n---\n"),
+            "Title\n\nClosed\nSub\n"
+        );
+        assert_eq!(to_plain_text("#hashtag"), "#hashtag");
+    }
+
+    #[test]
+    fn normalizes_list_markers_and_keeps_nesting() {
+        let content = "* one\n  + nested\n    - deeper\n1) first\n2. second\n";
+        assert_eq!(
+            to_plain_text(content),
+            "- one\n  - nested\n    - deeper\n1. first\n2. second\n"
+        );
+    }
+
+    #[test]
+    fn rewrites_links_and_images() {
+        assert_eq!(
+            to_plain_text("See [the docs](https://example.com) and ![a cat](cat.png)."),
+            "See the docs (https://example.com) and [image: a cat]."
+        );
+        assert_eq!(
+            to_plain_text("[https://example.com](https://example.com) <https://x.org>"),
+            "https://example.com https://x.org"
+        );
+    }
+
+    #[test]
+    fn strips_emphasis_and_code_spans() {
+        assert_eq!(
+            to_plain_text("**bold** _it_ ~~gone~~ `code` snake_case 2 * 3 \\*"),
+            "bold it gone code snake_case 2 * 3 *"
+        );
+    }
+
+    #[test]
+    fn dedents_code_fences_and_keeps_their_content() {
+        let content = "Text\n```rust\n    fn main() {\n        run();\n    }\n```\n";
+        assert_eq!(to_plain_text(content), "Text\nfn main() {\n    run();\n}\n");
+        assert_eq!(to_plain_text("```\n# not a heading"), "# not a heading");
+    }
+
+    #[test]
+    fn turns_tables_into_tab_separated_rows() {
+        let content = "| a | **b** |\n| --- | :---: |\n| 1 | 2 |\n";
+        assert_eq!(to_plain_text(content), "a\tb\n1\t2\n");
+    }
+
+    #[test]
+    fn drops_blockquote_markers() {
+        assert_eq!(to_plain_text("> quoted\n> > twice\n"), "quoted\ntwice\n");
+    }
+
+    #[test]
+    fn splits_paragraphs_by_kind() {
+        let paragraphs = to_paragraphs("# Title\n\nOne\nline.\n\n- item\n");
+        let summary: Vec<_> = paragraphs
+            .iter()
+            .map(|p| (p.kind, p.text.as_str(), p.start_line))
+            .collect();
+        assert!(summary.contains(&(ParagraphKind::Heading, "Title", 0)));
+        assert!(summary
+            .iter()
+            .any(|(kind, _, line)| *kind == ParagraphKind::Paragraph && *line == 2));
+        assert!(summary
+            .iter()
+            .any(|(kind, _, line)| *kind == ParagraphKind::ListItem && *line == 5));
+    }
+}
@@ -0,0 +1,184 @@
+//! PlantUML rendering to SVG, with a local PlantUML install or a PlantUML server
+
+use std::env;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The public server, used when no local PlantUML is installed and none is configured
+const DEFAULT_SERVER: &str = "https://www.plantuml.com/plantuml";
+
+/// How long to wait for a PlantUML server before giving up
+const SERVER_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long a local PlantUML may run before it is killed. Longer than SERVER_TIMEOUT
+/// because `java -jar` starts slowly.
+const LOCAL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where diagrams are rendered
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// A `plantuml` executable
+    Command(PathBuf),
+    /// A plantuml.jar, run with `java -jar`
+    Jar(PathBuf),
+    /// A PlantUML server base URL, e.g. `https://www.plantuml.com/plantuml`
+    Server(String),
+}
+
+impl Backend {
+    /// Pick a backend: a configured jar first, then `plantuml` or `plantuml.jar` on PATH,
+    /// then the configured server (or the public one).
+    pub fn select(jar: Option<&str>, server: Option<&str>) -> Backend {
+        if let Some(jar) = jar {
+            return Backend::Jar(PathBuf::from(jar));
+        }
+        if let Some(command) = find_on_path(EXECUTABLE_NAMES) {
+            return Backend::Command(command);
+        }
+        if let Some(jar) = find_on_path(&["plantuml.jar"]) {
+            return Backend::Jar(jar);
+        }
+        let server = server.map(str::trim).filter(|s| !s.is_empty());
+        Backend::Server(
+            server
+                .unwrap_or(DEFAULT_SERVER)
+                .trim_end_matches('/')
+                .to_string(),
+        )
+    }
+
+    /// Render `source` to an SVG document
+    pub fn render(&self, source: &str) -> Result<String, String> {
+        match self {
+            Backend::Command(path) => run_local(Command::new(path), source, LOCAL_TIMEOUT),
+            Backend::Jar(jar) => {
+                let mut command = Command::new("java");
+                command.arg("-jar").arg(jar);
+                run_local(command, source, LOCAL_TIMEOUT)
+            }
+            Backend::Server(url) => fetch_from_server(url, source),
+        }
+    }
+}
+
+#[cfg(windows)]
+const EXECUTABLE_NAMES: &[&str] = &["plantuml.exe", "plantuml.bat", "plantuml.cmd"];
+#[cfg(not(windows))]
+const EXECUTABLE_NAMES: &[&str] = &["plantuml"];
+
+/// The first of `names` found in a PATH directory
+fn find_on_path(names: &[&str]) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Pipe `source` through a local PlantUML, reading the SVG from stdout. The process is
+/// killed if it hasn't exited after `timeout`.
+fn run_local(mut command: Command, source: &str, timeout: Duration) -> Result<String, String> {
+    let mut child = command
+        .args(["-tsvg", "-pipe", "-charset", "UTF-8"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run PlantUML: {}", e))?;
+
+    // Write from another thread so a large diagram can't fill both pipes and deadlock
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = source.to_string();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let stdout = read_in_background(child.stdout.take().expect("stdout is piped"));
+    let stderr = read_in_background(child.stderr.take().expect("stderr is piped"));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(20)),
+            result => {
+                // Timed out, or the process can't be waited on: don't leave it running
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(match result {
+                    Err(e) => format!("Failed to run PlantUML: {}", e),
+                    _ => format!("PlantUML timed out after {}s", timeout.as_secs()),
+                });
+            }
+        }
+    };
+    let _ = writer.join();
+    let stdout = stdout.join().unwrap_or_default();
+    if !status.success() {
+        let stderr = stderr.join().unwrap_or_default();
+        let stderr = String::from_utf8_lossy(&stderr);
+        return Err(format!("PlantUML failed: {}", stderr.trim()));
+    }
+    String::from_utf8(stdout).map_err(|_| "PlantUML returned invalid SVG".to_string())
+}
+
+/// Read a child's output pipe to the end on another thread, so a full pipe can't
+/// stall the child while its exit is awaited
+fn read_in_background(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = pipe.read_to_end(&mut output);
+        output
+    })
+}
+
+/// Fetch the SVG from a PlantUML server. The source is sent hex-encoded (`~h`), which
+/// every server version accepts without PlantUML's deflate encoding.
+fn fetch_from_server(server: &str, source: &str) -> Result<String, String> {
+    let url = format!("{}/svg/~h{}", server, hex_encode(source.as_bytes()));
+    ureq::get(&url)
+        .timeout(SERVER_TIMEOUT)
+        .call()
+        .map_err(|e| format!("PlantUML server request failed: {}", e))?
+        .into_string()
+        .map_err(|e| format!("Failed to read PlantUML server response: {}", e))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_local_returns_stdout() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "cat"]);
+        assert_eq!(
+            run_local(command, "<svg/>", LOCAL_TIMEOUT),
+            Ok("<svg/>".to_string())
+        );
+    }
+
+    #[test]
+    fn run_local_reports_failures_with_stderr() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo bad diagram >&2; exit 1"]);
+        assert_eq!(
+            run_local(command, "", LOCAL_TIMEOUT),
+            Err("PlantUML failed: bad diagram".to_string())
+        );
+    }
+
+    #[test]
+    fn run_local_kills_a_process_that_runs_too_long() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "exec sleep 10"]);
+        let started = Instant::now();
+        assert!(run_local(command, "", Duration::from_millis(200))
+            .unwrap_err()
+            .contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
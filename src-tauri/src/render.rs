@@ -0,0 +1,401 @@
+//! Pure-Rust markdown to HTML rendering for non-webview output (export, stdout, serve)
+
+use crate::emoji;
+use crate::slug::slugify;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The stylesheet the viewer renders with, inlined into exported documents
+const GITHUB_MARKDOWN_CSS: &str = include_str!("../../ui/github-markdown.min.css");
+
+/// Layout around the rendered body in exported documents (matches the viewer)
+const EXPORT_LAYOUT_CSS: &str =
+    ".markdown-body { box-sizing: border-box; max-width: 980px; margin: 0 auto; padding: 32px; }";
+
+/// Options controlling HTML rendering
+#[derive(Clone)]
+pub struct RenderOptions {
+    /// GitHub Flavored Markdown extensions (tables, strikethrough, task lists)
+    pub gfm: bool,
+    /// Footnote syntax (`[^1]`)
+    pub footnotes: bool,
+    /// Replace `:shortcode:` sequences with emoji before rendering
+    pub emoji: bool,
+    /// Escape raw HTML and neutralize `javascript:` URLs
+    pub sanitize: bool,
+    /// Emit GitHub-style slug `id` attributes on headings
+    pub heading_ids: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            gfm: true,
+            footnotes: true,
+            emoji: false,
+            sanitize: true,
+            heading_ids: true,
+        }
+    }
+}
+
+impl RenderOptions {
+    fn parser_options(&self) -> Options {
+        let mut options = Options::ENABLE_HEADING_ATTRIBUTES;
+        if self.gfm {
+            options |=
+                Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+        }
+        if self.footnotes {
+            options |= Options::ENABLE_FOOTNOTES;
+        }
+        options
+    }
+}
+
+/// Render key/value metadata (e.g. front matter) as an HTML definition list
+pub fn metadata_block_html(entries: &[(String, String)]) -> String {
+    let mut out = String::from("<dl class=\"front-matter\">\n");
+    for (key, value) in entries {
+        out.push_str(&format!(
+            "<dt>{}</dt><dd>{}</dd>\n",
+            escape_html(key),
+            escape_html(value)
+        ));
+    }
+    out.push_str("</dl>\n");
+    out
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Render markdown to an HTML fragment
+pub fn render_markdown_to_html(content: &str, opts: &RenderOptions) -> String {
+    render_with_images(content, opts, None)
+}
+
+/// Render markdown to an HTML fragment, replacing each local image source with what
+/// `inline_image` returns for it (e.g. a data URI). Remote and `data:` sources, and
+/// images `inline_image` returns None for, are left as written.
+pub fn render_markdown_with_images(
+    content: &str,
+    opts: &RenderOptions,
+    inline_image: &dyn Fn(&str) -> Option<String>,
+) -> String {
+    render_with_images(content, opts, Some(inline_image))
+}
+
+fn render_with_images(
+    content: &str,
+    opts: &RenderOptions,
+    inline_image: Option<&dyn Fn(&str) -> Option<String>>,
+) -> String {
+    let source = if opts.emoji {
+        Cow::Owned(emoji::replace_shortcodes(content))
+    } else {
+        Cow::Borrowed(content)
+    };
+
+    let mut events: Vec<Event> = Parser::new_ext(&source, opts.parser_options())
+        .map(|event| {
+            if opts.sanitize {
+                sanitize_event(event)
+            } else {
+                event
+            }
+        })
+        .collect();
+
+    if opts.heading_ids {
+        assign_heading_ids(&mut events);
+    }
+
+    if let Some(inline_image) = inline_image {
+        for event in events.iter_mut() {
+            if let Event::Start(Tag::Image { dest_url, .. }) = event {
+                if is_local_src(dest_url) {
+                    if let Some(inlined) = inline_image(dest_url) {
+                        *dest_url = CowStr::from(inlined);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut output = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut output, events.into_iter());
+    output
+}
+
+/// Wrap a rendered fragment in a complete HTML document with the viewer's stylesheet
+/// inlined, so it displays the same without glance or network access
+pub fn standalone_document(title: &str, body_html: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>\n{}\n{}\n</style>\n</head>\n<body>\n\
+         <article class=\"markdown-body\">\n{}</article>\n</body>\n</html>\n",
+        escape_html(title),
+        GITHUB_MARKDOWN_CSS,
+        EXPORT_LAYOUT_CSS,
+        body_html
+    )
+}
+
+/// Read an image file as a `data:` URI, or None if it can't be read, is larger than
+/// `max_bytes`, or isn't a known image type
+pub fn image_data_uri(path: &Path, max_bytes: u64) -> Option<String> {
+    let mime = image_mime(path)?;
+    if fs::metadata(path).ok()?.len() > max_bytes {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    Some(format!("data:{};base64,{}", mime, base64_encode(&bytes)))
+}
+
+/// MIME type of an image file from its extension, or None if it isn't a known image type
+pub fn image_mime(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "svg" => Some("image/svg+xml"),
+        "webp" => Some("image/webp"),
+        "bmp" => Some("image/bmp"),
+        "ico" => Some("image/x-icon"),
+        "avif" => Some("image/avif"),
+        _ => None,
+    }
+}
+
+pub fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn is_local_src(src: &str) -> bool {
+    let lower = src.trim().to_lowercase();
+    !(lower.is_empty()
+        || lower.starts_with("http://")
+        || lower.starts_with("https://")
+        || lower.starts_with("data:")
+        || lower.starts_with('#'))
+}
+
+/// Give every heading without an explicit `{#id}` a slug id derived from its text
+fn assign_heading_ids(events: &mut [Event]) {
+    let mut seen = HashMap::new();
+    let mut i = 0;
+
+    while i < events.len() {
+        if let Event::Start(Tag::Heading { id: None, .. }) = &events[i] {
+            let mut text = String::new();
+            let mut j = i + 1;
+            while j < events.len() && !matches!(events[j], Event::End(TagEnd::Heading(_))) {
+                if let Event::Text(t) | Event::Code(t) = &events[j] {
+                    text.push_str(t);
+                }
+                j += 1;
+            }
+
+            let slug = slugify(&text, &mut seen);
+            if let Event::Start(Tag::Heading { id, .. }) = &mut events[i] {
+                *id = Some(CowStr::from(slug));
+            }
+            i = j;
+        }
+        i += 1;
+    }
+}
+
+/// Escape raw HTML and replace script URLs with a harmless anchor
+fn sanitize_event(event: Event) -> Event {
+    match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if is_unsafe_url(&dest_url) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: CowStr::Borrowed("#"),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if is_unsafe_url(&dest_url) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: CowStr::Borrowed(""),
+            title,
+            id,
+        }),
+        other => other,
+    }
+}
+
+fn is_unsafe_url(url: &str) -> bool {
+    let lower = url.trim().to_lowercase();
+    lower.starts_with("javascript:") || lower.starts_with("vbscript:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = "# Hello World\n\n\
+        Some *em*, **strong** and ~~gone~~ text with [a link](https://example.com).\n\n\
+        - one\n- two\n\n\
+        ```rust\nfn main() {}\n```\n";
+
+    #[test]
+    fn renders_a_representative_document() {
+        assert_eq!(
+            render_markdown_to_html(DOCUMENT, &RenderOptions::default()),
+            "<h1 id=\"hello-world\">Hello World</h1>\n\
+             <p>Some <em>em</em>, <strong>strong</strong> and <del>gone</del> text with \
+             <a href=\"https://example.com\">a link</a>.</p>\n\
+             <ul>\n<li>one</li>\n<li>two</li>\n</ul>\n\
+             <pre><code class=\"language-rust\">fn main() {}\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn gfm_extensions_follow_the_flag() {
+        let content = "| a | b |\n| --- | --- |\n| 1 | 2 |\n\n~~gone~~\n";
+        let html = render_markdown_to_html(content, &RenderOptions::default());
+        assert!(html.contains("<th>a</th>"));
+        assert!(html.contains("<td>2</td>"));
+        assert!(html.contains("<del>gone</del>"));
+
+        let plain = RenderOptions {
+            gfm: false,
+            ..RenderOptions::default()
+        };
+        let html = render_markdown_to_html(content, &plain);
+        assert!(!html.contains("<table>"));
+        assert!(html.contains("~~gone~~"));
+    }
+
+    #[test]
+    fn heading_ids_are_unique_and_keep_explicit_ids() {
+        let content = "# Intro\n\n## Intro\n\n## The `run` command\n\n## Custom {#mine}\n";
+        let html = render_markdown_to_html(content, &RenderOptions::default());
+        assert!(html.contains("<h1 id=\"intro\">Intro</h1>"));
+        assert!(html.contains("<h2 id=\"intro-1\">Intro</h2>"));
+        assert!(html.contains("<h2 id=\"the-run-command\">The <code>run</code> command</h2>"));
+        assert!(html.contains("<h2 id=\"mine\">Custom</h2>"));
+
+        let no_ids = RenderOptions {
+            heading_ids: false,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render_markdown_to_html("# Intro\n", &no_ids),
+            "<h1>Intro</h1>\n"
+        );
+    }
+
+    #[test]
+    fn sanitizing_escapes_raw_html_and_script_urls() {
+        let content = "<script>alert(1)</script>\n\n[x](javascript:alert(1)) <b>bold</b>\n";
+        let html = render_markdown_to_html(content, &RenderOptions::default());
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(html.contains("<a href=\"#\">x</a>"));
+        assert!(html.contains("&lt;b&gt;bold&lt;/b&gt;"));
+
+        let raw = RenderOptions {
+            sanitize: false,
+            ..RenderOptions::default()
+        };
+        assert!(render_markdown_to_html(content, &raw).contains("<script>"));
+    }
+
+    #[test]
+    fn emoji_shortcodes_follow_the_flag() {
+        let with_emoji = RenderOptions {
+            emoji: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render_markdown_to_html("Ship :rocket:\n", &with_emoji),
+            "<p>Ship 🚀</p>\n"
+        );
+        assert_eq!(
+            render_markdown_to_html("Ship :rocket:\n", &RenderOptions::default()),
+            "<p>Ship :rocket:</p>\n"
+        );
+    }
+
+    #[test]
+    fn inlines_only_local_images() {
+        let content = "![a](a.png) ![b](https://example.com/b.png) ![c](missing.png)\n";
+        let html = render_markdown_with_images(content, &RenderOptions::default(), &|src: &str| {
+            (src == "a.png").then(|| "data:image/png;base64,AA==".to_string())
+        });
+        assert!(html.contains("src=\"data:image/png;base64,AA==\""));
+        assert!(html.contains("src=\"https://example.com/b.png\""));
+        assert!(html.contains("src=\"missing.png\""));
+    }
+
+    #[test]
+    fn standalone_document_escapes_the_title() {
+        let page = standalone_document("<A & B>", "<p>x</p>\n");
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains("<title>&lt;A &amp; B&gt;</title>"));
+        assert!(page.contains("<article class=\"markdown-body\">\n<p>x</p>\n</article>"));
+    }
+
+    #[test]
+    fn encodes_base64_with_padding() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn image_mime_ignores_extension_case() {
+        assert_eq!(image_mime(Path::new("a.PNG")), Some("image/png"));
+        assert_eq!(image_mime(Path::new("a.jpeg")), Some("image/jpeg"));
+        assert_eq!(image_mime(Path::new("a.md")), None);
+        assert_eq!(image_mime(Path::new("png")), None);
+    }
+}
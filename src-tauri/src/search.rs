@@ -0,0 +1,88 @@
+//! Full-text search over the raw document
+
+use regex::RegexBuilder;
+
+/// Matches beyond this are not reported
+const MAX_MATCHES: usize = 1000;
+
+/// Characters of context kept on each side of a match in its snippet
+const SNIPPET_CONTEXT: usize = 40;
+
+/// One search hit in the raw document
+#[derive(Clone, serde::Serialize)]
+pub struct SearchMatch {
+    /// Line of the match (0-indexed)
+    pub line: usize,
+    /// Byte offset of the match in the document
+    pub offset: usize,
+    /// Length of the match in bytes
+    pub length: usize,
+    /// The matched line, shortened around the match
+    pub snippet: String,
+}
+
+/// Find `query` in `content`, in document order. The query is a literal string unless
+/// `regex` is set. Matches don't span lines.
+pub fn find_matches(
+    content: &str,
+    query: &str,
+    case_sensitive: bool,
+    regex: bool,
+) -> Result<Vec<SearchMatch>, String> {
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let pattern = if regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let matcher = RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))?;
+
+    let mut matches = Vec::new();
+    let mut line_start = 0;
+    for (line_number, line) in content.split('\n').enumerate() {
+        for found in matcher.find_iter(line) {
+            // Empty matches (e.g. `^` or `a*`) aren't useful hits
+            if found.is_empty() {
+                continue;
+            }
+            matches.push(SearchMatch {
+                line: line_number,
+                offset: line_start + found.start(),
+                length: found.len(),
+                snippet: snippet(line, found.start(), found.end()),
+            });
+            if matches.len() >= MAX_MATCHES {
+                return Ok(matches);
+            }
+        }
+        line_start += line.len() + 1;
+    }
+    Ok(matches)
+}
+
+/// The line around `start..end`, with `…` where it was cut
+fn snippet(line: &str, start: usize, end: usize) -> String {
+    let before: Vec<char> = line[..start].chars().collect();
+    let after: Vec<char> = line[end..].trim_end_matches('\r').chars().collect();
+
+    let mut out = String::new();
+    if before.len() > SNIPPET_CONTEXT {
+        out.push('…');
+        out.extend(&before[before.len() - SNIPPET_CONTEXT..]);
+    } else {
+        out.extend(&before);
+    }
+    out.push_str(&line[start..end]);
+    if after.len() > SNIPPET_CONTEXT {
+        out.extend(&after[..SNIPPET_CONTEXT]);
+        out.push('…');
+    } else {
+        out.extend(&after);
+    }
+    out.trim().to_string()
+}
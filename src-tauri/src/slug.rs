@@ -0,0 +1,33 @@
+//! GitHub-style heading slugs for linkable anchors
+
+use std::collections::HashMap;
+
+/// Build a GitHub-compatible slug for a heading title: lowercase, whitespace to
+/// hyphens, punctuation stripped. Repeated slugs get `-1`, `-2`, ... suffixes.
+pub fn slugify(title: &str, seen: &mut HashMap<String, usize>) -> String {
+    let base: String = title
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                Some(c)
+            } else if c.is_whitespace() {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    match seen.get_mut(&base) {
+        Some(count) => {
+            *count += 1;
+            format!("{}-{}", base, count)
+        }
+        None => {
+            seen.insert(base.clone(), 0);
+            base
+        }
+    }
+}
@@ -90,6 +90,9 @@
                     filters: [{
                         name: 'Markdown',
                         extensions: ['md', 'markdown']
+                    }, {
+                        name: 'AsciiDoc',
+                        extensions: ['adoc', 'asciidoc']
                     }],
                     multiple: false
                 });